@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use crate::expr::Expr;
+use crate::expr::{Expr, Pattern};
 use crate::stmt::Stmt;
 
 pub fn print(expr: &Expr) -> String {
@@ -8,28 +8,57 @@ pub fn print(expr: &Expr) -> String {
         Expr::Binary(left, op, right) => {
             format!("({} {} {})", op.lexeme, print(left), print(right))
         }
-        Expr::Grouping(expr) => {
+        Expr::Call(callee, _, arguments) => {
+            let arguments: Vec<String> = arguments.iter().map(print).collect();
+            format!("(call {} {})", print(callee), arguments.join(" "))
+        }
+        Expr::Grouping(expr, _) => {
             format!("(group {})", print(expr))
         }
         Expr::Unary(op, right) => {
             format!("({} {})", op.lexeme, print(right))
         }
-        Expr::LiteralNum(opt) => print_literal(opt),
-        Expr::LiteralStr(opt) => print_literal(opt),
-        Expr::LiteralBool(opt) => print_literal(opt),
+        Expr::Range(start, op, end, step) => match step {
+            Some(step) => format!("({} {} {} {})", op.lexeme, print(start), print(end), print(step)),
+            None => format!("({} {} {})", op.lexeme, print(start), print(end)),
+        },
+        Expr::LiteralNum(opt, _) => print_literal(opt),
+        Expr::LiteralStr(opt, _) => print_literal(opt),
+        Expr::LiteralBool(opt, _) => print_literal(opt),
         Expr::Variable(_) => { todo!() }
         Expr::Assign(_, _) => { todo!() }
+        Expr::AssignPattern(pattern, value) => {
+            format!("(= {} {})", print_pattern(pattern), print(value))
+        }
+        Expr::Tuple(elements, _) => {
+            let elements: Vec<String> = elements.iter().map(print).collect();
+            format!("(tuple {})", elements.join(" "))
+        }
     }
 }
 
 pub fn print_stmt(stmt: &Stmt) -> String {
     match stmt {
-        Stmt::Expression(expr) => print(expr),
-        Stmt::Print(expr) => format!("(print {})", print(expr)),
+        Stmt::Expression(expr, _) => print(expr),
+        Stmt::Print(expr, _) => format!("(print {})", print(expr)),
         _ => { todo!() }
     }
 }
 
+fn print_pattern(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Name(name) => name.lexeme.clone(),
+        Pattern::Tuple(patterns) => {
+            let patterns: Vec<String> = patterns.iter().map(print_pattern).collect();
+            format!("({})", patterns.join(" "))
+        }
+        Pattern::Variant(name, patterns) => {
+            let patterns: Vec<String> = patterns.iter().map(print_pattern).collect();
+            format!("({} {})", name.lexeme, patterns.join(" "))
+        }
+    }
+}
+
 fn print_literal<T: Display>(opt: &Option<T>) -> String {
     match opt {
         Some(val) => format!("{}", val),
@@ -40,7 +69,7 @@ fn print_literal<T: Display>(opt: &Option<T>) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::token::{Token, TokenType};
+    use crate::token::{Span, Token, TokenType};
 
     use super::*;
 
@@ -48,10 +77,11 @@ mod tests {
     fn test_print() {
         let left = Expr::Unary(
             Token::new(TokenType::Minus, "-", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(123.0))),
+            Box::new(Expr::LiteralNum(Some(123.0), Span::default())),
         );
         let right = Expr::Grouping(
-            Box::new(Expr::LiteralNum(Some(45.67)))
+            Box::new(Expr::LiteralNum(Some(45.67), Span::default())),
+            Span::default(),
         );
         let expr = Expr::Binary(
             Box::new(left),
@@ -0,0 +1,312 @@
+//! Stable error codes. A code names a kind of mistake rather than a message, so messages can be
+//! reworded without breaking links to `rlox explain` output. Codes are never reused or renumbered:
+//! E00xx are lexical, E01xx syntax, E02xx resolution, E03xx type, E04xx runtime and E05xx I/O errors.
+
+pub const UNEXPECTED_CHARACTER: &str = "E0001";
+pub const UNTERMINATED_STRING: &str = "E0002";
+pub const UNTERMINATED_COMMENT: &str = "E0003";
+pub const MALFORMED_NUMBER: &str = "E0004";
+pub const NUMBER_TOO_LARGE: &str = "E0005";
+
+pub const EXPECTED_EXPRESSION: &str = "E0100";
+pub const MISSING_SEMICOLON: &str = "E0101";
+pub const UNCLOSED_DELIMITER: &str = "E0102";
+pub const INVALID_ASSIGNMENT_TARGET: &str = "E0103";
+pub const EXPECTED_IDENTIFIER: &str = "E0104";
+pub const UNEXPECTED_TOKEN: &str = "E0105";
+
+pub const UNDEFINED_VARIABLE: &str = "E0200";
+
+pub const UNKNOWN_TYPE: &str = "E0300";
+pub const MISMATCHED_TYPES: &str = "E0301";
+pub const INVALID_OPERANDS: &str = "E0302";
+pub const NOT_ITERABLE: &str = "E0303";
+pub const NOT_CALLABLE: &str = "E0304";
+pub const INVALID_PATTERN: &str = "E0305";
+
+pub const OPERAND_TYPE: &str = "E0400";
+pub const ITERATE_NON_ITERABLE: &str = "E0401";
+pub const CALL_NON_CALLABLE: &str = "E0402";
+pub const ARITY_MISMATCH: &str = "E0403";
+pub const DESTRUCTURE_MISMATCH: &str = "E0404";
+pub const ZERO_STEP: &str = "E0405";
+pub const HOST_FUNCTION_FAILED: &str = "E0406";
+pub const NON_FINITE_RANGE: &str = "E0407";
+
+pub const CANNOT_READ: &str = "E0500";
+pub const CANNOT_WRITE: &str = "E0501";
+
+/// The long-form documentation behind a code, printed by `rlox explain`.
+pub struct Explanation {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub erroneous: &'static str,
+    pub corrected: &'static str,
+}
+
+impl Explanation {
+    pub fn render(&self) -> String {
+        format!(
+            "{}: {}\n\n{}\n\nErroneous code example:\n\n{}\nCorrected:\n\n{}",
+            self.code, self.title, self.description, indent(self.erroneous), indent(self.corrected)
+        )
+    }
+}
+
+fn indent(code: &str) -> String {
+    code.lines().map(|line| format!("    {}\n", line)).collect()
+}
+
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS.iter().find(|explanation| explanation.code.eq_ignore_ascii_case(code))
+}
+
+pub static EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: UNEXPECTED_CHARACTER,
+        title: "Unexpected character",
+        description: "The source contains a character that cannot start any token, such as '@' or '#'. \
+Outside of strings and comments, Lox only uses letters, digits, '_', whitespace and its operators and punctuation.",
+        erroneous: "var total = 1 # 2;",
+        corrected: "var total = 1 + 2;",
+    },
+    Explanation {
+        code: UNTERMINATED_STRING,
+        title: "Unterminated string",
+        description: "A string literal was opened with '\"' but the file ended before the closing '\"'. \
+Strings may span lines, so the missing quote is often far above where the scanner gave up.",
+        erroneous: "print \"hello;",
+        corrected: "print \"hello\";",
+    },
+    Explanation {
+        code: UNTERMINATED_COMMENT,
+        title: "Unterminated block comment",
+        description: "A '/*' comment was never closed. Block comments nest, so every '/*' inside a comment \
+needs its own '*/' as well.",
+        erroneous: "/* outer /* inner */\nprint 1;",
+        corrected: "/* outer /* inner */ */\nprint 1;",
+    },
+    Explanation {
+        code: MALFORMED_NUMBER,
+        title: "Malformed number literal",
+        description: "A number literal doesn't follow the number syntax. Decimal numbers need digits on both sides \
+of a '.', an exponent needs digits after 'e', '0x', '0b' and '0o' literals need at least one digit valid for \
+their base, and the '_' separator may only appear between two digits.",
+        erroneous: "var x = 1._5;\nvar mask = 0b102;",
+        corrected: "var x = 1.5;\nvar mask = 0b101;",
+    },
+    Explanation {
+        code: NUMBER_TOO_LARGE,
+        title: "Number literal is too large",
+        description: "A number literal is larger than the largest number Lox can represent.",
+        erroneous: "var big = 1e99;",
+        corrected: "var big = 1e38;",
+    },
+    Explanation {
+        code: EXPECTED_EXPRESSION,
+        title: "Expected expression",
+        description: "The parser needed a value, such as a literal, a variable or a parenthesized expression, \
+but found something else. This usually means an operand is missing on one side of an operator.",
+        erroneous: "var a = 1 + ;",
+        corrected: "var a = 1 + 2;",
+    },
+    Explanation {
+        code: MISSING_SEMICOLON,
+        title: "Missing semicolon",
+        description: "Every statement ends with ';'. When it is missing, the error points at whatever follows \
+the statement, which is often the start of the next line.",
+        erroneous: "var a = 1\nprint a;",
+        corrected: "var a = 1;\nprint a;",
+    },
+    Explanation {
+        code: UNCLOSED_DELIMITER,
+        title: "Unclosed delimiter",
+        description: "A '(' or '{' was opened but the matching ')' or '}' never came, or something unexpected \
+appeared before it.",
+        erroneous: "print (1 + 2;",
+        corrected: "print (1 + 2);",
+    },
+    Explanation {
+        code: INVALID_ASSIGNMENT_TARGET,
+        title: "Invalid assignment target",
+        description: "The left-hand side of '=' must be a variable, or a tuple or enum pattern of variables. \
+Other expressions, such as literals or arithmetic, can't be assigned to.",
+        erroneous: "var a = 1;\na + 1 = 2;",
+        corrected: "var a = 1;\na = 2 - 1;",
+    },
+    Explanation {
+        code: EXPECTED_IDENTIFIER,
+        title: "Expected a name",
+        description: "A variable, enum, variant, field or type name was expected here. Names start with a letter \
+or '_' and can't be keywords.",
+        erroneous: "var class = 1;",
+        corrected: "var kind = 1;",
+    },
+    Explanation {
+        code: UNEXPECTED_TOKEN,
+        title: "Unexpected token",
+        description: "A specific keyword or punctuation mark was required at this point of the grammar, such as \
+'(' after 'for', 'in' between loop variables and the iterable, '{' before an enum body or '=' after a \
+destructuring pattern.",
+        erroneous: "for (x of 0..3) print x;",
+        corrected: "for (x in 0..3) print x;",
+    },
+    Explanation {
+        code: UNDEFINED_VARIABLE,
+        title: "Undefined variable",
+        description: "A variable was read or assigned before it was declared with 'var', or outside the block \
+that declares it.",
+        erroneous: "count = 1;",
+        corrected: "var count = 1;",
+    },
+    Explanation {
+        code: UNKNOWN_TYPE,
+        title: "Unknown type",
+        description: "A type annotation names a type that doesn't exist. The types are Number, String, Bool, \
+Range, Nil and Any, tuples of them such as (Number, String), and optional types such as Number?.",
+        erroneous: "var n: Int = 1;",
+        corrected: "var n: Number = 1;",
+    },
+    Explanation {
+        code: MISMATCHED_TYPES,
+        title: "Mismatched types",
+        description: "A value is stored in a variable annotated with a type that doesn't accept it. Annotated \
+variables keep their type for their whole life, so later assignments must match too. Use an optional type \
+like Number? for variables that may hold nil.",
+        erroneous: "var n: Number = 1;\nn = \"one\";",
+        corrected: "var n: Number = 1;\nn = 2;",
+    },
+    Explanation {
+        code: INVALID_OPERANDS,
+        title: "Invalid operand types",
+        description: "An operator is applied to values whose types it doesn't support, as far as the checker \
+can tell before running the program. Arithmetic and comparisons need numbers, '+' needs two numbers or two \
+strings, '!' needs a bool and 'in' needs a number and a range, or two strings.",
+        erroneous: "print 1 + \"a\";",
+        corrected: "print \"1\" + \"a\";",
+    },
+    Explanation {
+        code: NOT_ITERABLE,
+        title: "Value is not iterable",
+        description: "A for-in loop goes over a value that can't be iterated. Strings, ranges and tuples are \
+iterable.",
+        erroneous: "for (i in 10) print i;",
+        corrected: "for (i in 0..10) print i;",
+    },
+    Explanation {
+        code: NOT_CALLABLE,
+        title: "Value is not callable",
+        description: "A value that isn't a function or an enum variant constructor is called.",
+        erroneous: "var n: Number = 1;\nn(2);",
+        corrected: "enum Box { Full(n) }\nFull(2);",
+    },
+    Explanation {
+        code: INVALID_PATTERN,
+        title: "Pattern doesn't fit the value",
+        description: "A destructuring pattern can never match the value's type, for example a tuple pattern \
+against a number.",
+        erroneous: "var (a, b) = 1;",
+        corrected: "var (a, b) = (1, 2);",
+    },
+    Explanation {
+        code: OPERAND_TYPE,
+        title: "Invalid operand types at runtime",
+        description: "An operator received values of types it doesn't support while the program ran. The checker \
+can't see through unannotated variables, so these errors surface at runtime instead.",
+        erroneous: "var a = \"1\";\nprint a - 1;",
+        corrected: "var a = 1;\nprint a - 1;",
+    },
+    Explanation {
+        code: ITERATE_NON_ITERABLE,
+        title: "Iterating a value that is not iterable",
+        description: "A for-in loop received a value that can't be iterated while the program ran. Strings, \
+ranges and tuples are iterable.",
+        erroneous: "var n = 3;\nfor (i in n) print i;",
+        corrected: "var n = 3;\nfor (i in 0..n) print i;",
+    },
+    Explanation {
+        code: CALL_NON_CALLABLE,
+        title: "Calling a value that is not callable",
+        description: "A value that isn't a function or an enum variant constructor was called while the program \
+ran.",
+        erroneous: "var f = \"print\";\nf(1);",
+        corrected: "enum Wrapper { Wrap(value) }\nvar f = Wrap;\nf(1);",
+    },
+    Explanation {
+        code: ARITY_MISMATCH,
+        title: "Wrong number of arguments",
+        description: "A function or constructor was called with a different number of arguments than it declares.",
+        erroneous: "enum Shape { Rect(w, h) }\nRect(1);",
+        corrected: "enum Shape { Rect(w, h) }\nRect(1, 2);",
+    },
+    Explanation {
+        code: DESTRUCTURE_MISMATCH,
+        title: "Destructuring mismatch",
+        description: "A destructuring pattern doesn't match the value it was given: a tuple has a different \
+number of elements than the pattern, or a value is a different enum variant than the one the pattern names.",
+        erroneous: "var point = (1, 2, 3);\nvar (x, y) = point;",
+        corrected: "var point = (1, 2, 3);\nvar (x, y, z) = point;",
+    },
+    Explanation {
+        code: ZERO_STEP,
+        title: "Range step is zero",
+        description: "A range's step must be a non-zero number; a zero step would never reach the end of the range.",
+        erroneous: "for (i in 0..10 step 0) print i;",
+        corrected: "for (i in 0..10 step 2) print i;",
+    },
+    Explanation {
+        code: NON_FINITE_RANGE,
+        title: "Range is not finite",
+        description: "A range's bounds must be finite numbers and its step must not be NaN. Dividing by zero \
+is the usual way to end up with an infinite or NaN value.",
+        erroneous: "var n = 0;\nfor (i in 0..=1 / n) print i;",
+        corrected: "var n = 2;\nfor (i in 0..=1 / n) print i;",
+    },
+    Explanation {
+        code: HOST_FUNCTION_FAILED,
+        title: "Host function failed",
+        description: "A function provided by the program embedding Lox rejected its arguments or reported an error. \
+The notes under the error describe what the function expected.",
+        erroneous: "// with a host function `sqrt` that takes a number\nsqrt(\"4\");",
+        corrected: "sqrt(4);",
+    },
+    Explanation {
+        code: CANNOT_READ,
+        title: "Cannot read script",
+        description: "The script file doesn't exist, isn't readable or isn't valid UTF-8.",
+        erroneous: "$ rlox missing.lox",
+        corrected: "$ rlox script.lox",
+    },
+    Explanation {
+        code: CANNOT_WRITE,
+        title: "Cannot write output",
+        description: "A 'print' statement failed to write its output, for example because stdout was closed by \
+the program reading it.",
+        erroneous: "$ rlox script.lox | head -0",
+        corrected: "$ rlox script.lox | head",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique_and_explained() {
+        let codes: HashSet<&str> = EXPLANATIONS.iter().map(|explanation| explanation.code).collect();
+        assert_eq!(codes.len(), EXPLANATIONS.len());
+        assert!(codes.iter().all(|code| code.len() == 5 && code.starts_with('E')));
+        assert!(explain("e0004").is_some());
+        assert!(explain("E9999").is_none());
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = explain(MISSING_SEMICOLON).unwrap().render();
+        assert!(rendered.starts_with("E0101: Missing semicolon\n\n"));
+        assert!(rendered.ends_with("Corrected:\n\n    var a = 1;\n    print a;\n"));
+    }
+}
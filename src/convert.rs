@@ -0,0 +1,364 @@
+use std::error;
+use std::fmt;
+
+use crate::codes;
+use crate::error::Error;
+use crate::interpreter::Value;
+use crate::range::Range;
+use crate::variant::Variant;
+
+/// A Lox value that doesn't fit the Rust type it was converted to, e.g. a string passed where an `f32` is wanted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionError {
+    pub expected: String,
+    pub found: String,
+}
+
+impl ConversionError {
+    fn new(expected: &str, found: &Value) -> Self {
+        Self {
+            expected: expected.to_string(),
+            found: found.type_name().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {} but got {}", self.expected, self.found)
+    }
+}
+
+impl error::Error for ConversionError {}
+
+/// Converts a Rust value into a Lox value. `Vec`s and tuples become Lox tuples, `None` becomes nil.
+/// Maps have no conversion since Lox has no map value.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+/// Converts a Lox value back into a Rust value, failing if it has the wrong type.
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Result<Self, ConversionError>;
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        Ok(value)
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Nil
+    }
+}
+
+impl FromValue for () {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Nil => Ok(()),
+            value => Err(ConversionError::new("nil", &value)),
+        }
+    }
+}
+
+impl IntoValue for f32 {
+    fn into_value(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Number(n) => Ok(n),
+            value => Err(ConversionError::new("number", &value)),
+        }
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        Value::Number(self as f32)
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        f32::from_value(value).map(f64::from)
+    }
+}
+
+/// Integers are numbers without a fractional part that fit the integer type.
+/// `MAX as f32` rounds up to a power of two that doesn't fit, so it is an exclusive bound.
+macro_rules! integer_conversions {
+    ($($int:ty),*) => {$(
+        impl IntoValue for $int {
+            fn into_value(self) -> Value {
+                Value::Number(self as f32)
+            }
+        }
+
+        impl FromValue for $int {
+            fn from_value(value: Value) -> Result<Self, ConversionError> {
+                let n = f32::from_value(value)?;
+                if n.fract() != 0.0 || n < <$int>::MIN as f32 || n >= <$int>::MAX as f32 {
+                    return Err(ConversionError {
+                        expected: stringify!($int).to_string(),
+                        found: format!("number {}", n),
+                    });
+                }
+                Ok(n as $int)
+            }
+        }
+    )*};
+}
+
+integer_conversions!(i32, i64, u32, u64, usize);
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Bool(self)
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            value => Err(ConversionError::new("bool", &value)),
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::String(s) => Ok(s),
+            value => Err(ConversionError::new("string", &value)),
+        }
+    }
+}
+
+impl IntoValue for Range {
+    fn into_value(self) -> Value {
+        Value::Range(self)
+    }
+}
+
+impl FromValue for Range {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Range(range) => Ok(range),
+            value => Err(ConversionError::new("range", &value)),
+        }
+    }
+}
+
+impl IntoValue for Variant {
+    fn into_value(self) -> Value {
+        Value::Variant(self)
+    }
+}
+
+impl FromValue for Variant {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Variant(variant) => Ok(variant),
+            value => Err(ConversionError::new("enum", &value)),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        self.map_or(Value::Nil, IntoValue::into_value)
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Nil => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::Tuple(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Tuple(values) => values.into_iter().map(T::from_value).collect(),
+            value => Err(ConversionError::new("tuple", &value)),
+        }
+    }
+}
+
+macro_rules! tuple_conversions {
+    ($len:literal: $($name:ident),+) => {
+        impl<$($name: IntoValue),+> IntoValue for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn into_value(self) -> Value {
+                let ($($name,)+) = self;
+                Value::Tuple(vec![$($name.into_value()),+])
+            }
+        }
+
+        impl<$($name: FromValue),+> FromValue for ($($name,)+) {
+            fn from_value(value: Value) -> Result<Self, ConversionError> {
+                match value {
+                    Value::Tuple(values) if values.len() == $len => {
+                        let mut values = values.into_iter();
+                        Ok(($($name::from_value(values.next().unwrap())?,)+))
+                    }
+                    Value::Tuple(values) => Err(ConversionError {
+                        expected: format!("tuple of {} elements", $len),
+                        found: format!("tuple of {} elements", values.len()),
+                    }),
+                    value => Err(ConversionError::new(&format!("tuple of {} elements", $len), &value)),
+                }
+            }
+        }
+    };
+}
+
+tuple_conversions!(1: A);
+tuple_conversions!(2: A, B);
+tuple_conversions!(3: A, B, C);
+tuple_conversions!(4: A, B, C, D);
+
+/// What a host function may return: any convertible value, or a `Result` whose error becomes a runtime error.
+pub trait IntoHostResult {
+    fn into_host_result(self) -> Result<Value, Error>;
+}
+
+impl<T: IntoValue> IntoHostResult for T {
+    fn into_host_result(self) -> Result<Value, Error> {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue, E: fmt::Display> IntoHostResult for Result<T, E> {
+    fn into_host_result(self) -> Result<Value, Error> {
+        self.map(IntoValue::into_value).map_err(|e| Error::unlocated(codes::HOST_FUNCTION_FAILED, &e.to_string()))
+    }
+}
+
+/// A plain Rust closure usable as a host function, e.g. `|a: f32, b: f32| a + b`.
+/// Its arity and argument conversions come from its signature; `Args` only tells the impls apart.
+pub trait HostFunction<Args>: Send + Sync + 'static {
+    fn arity(&self) -> usize;
+    /// Calls the function registered as `name`, which errors about its arguments mention.
+    fn invoke(&self, name: &str, arguments: &[Value]) -> Result<Value, Error>;
+}
+
+macro_rules! host_functions {
+    ($len:literal: $($arg:ident),*) => {
+        impl<F, R, $($arg),*> HostFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + Send + Sync + 'static,
+            R: IntoHostResult,
+            $($arg: FromValue,)*
+        {
+            fn arity(&self) -> usize {
+                $len
+            }
+
+            #[allow(unused_variables, unused_mut)]
+            fn invoke(&self, name: &str, arguments: &[Value]) -> Result<Value, Error> {
+                let mut arguments = arguments.iter().cloned().enumerate();
+                self($({
+                    let (i, argument) = arguments.next().unwrap();
+                    $arg::from_value(argument).map_err(|e| {
+                        let message = format!("Invalid argument {} to '{}': {}.", i + 1, name, e);
+                        Error::unlocated(codes::HOST_FUNCTION_FAILED, &message)
+                    })?
+                }),*).into_host_result()
+            }
+        }
+    };
+}
+
+host_functions!(0:);
+host_functions!(1: A);
+host_functions!(2: A, B);
+host_functions!(3: A, B, C);
+host_functions!(4: A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips() {
+        assert_eq!(f32::from_value(1.5.into_value()), Ok(1.5));
+        assert_eq!(i64::from_value((-3i64).into_value()), Ok(-3));
+        assert_eq!(String::from_value("lox".into_value()), Ok("lox".to_string()));
+        assert_eq!(Option::<bool>::from_value(Value::Nil), Ok(None));
+        assert_eq!(Option::<bool>::from_value(true.into_value()), Ok(Some(true)));
+        assert_eq!(Vec::<u32>::from_value(vec![1u32, 2, 3].into_value()), Ok(vec![1, 2, 3]));
+        assert_eq!(<(String, f32)>::from_value(("a", 2.0).into_value()), Ok(("a".to_string(), 2.0)));
+        assert_eq!(().into_value(), Value::Nil);
+    }
+
+    #[test]
+    fn test_conversion_errors() {
+        let error = f32::from_value(Value::String("a".to_string())).unwrap_err();
+        assert_eq!(error.to_string(), "expected number but got string");
+        assert_eq!(u32::from_value(Value::Number(-1.0)).unwrap_err().to_string(), "expected u32 but got number -1");
+        assert_eq!(i32::from_value(Value::Number(1.5)).unwrap_err().to_string(), "expected i32 but got number 1.5");
+        assert_eq!(
+            <(f32, f32)>::from_value((1.0, 2.0, 3.0).into_value()).unwrap_err().to_string(),
+            "expected tuple of 2 elements but got tuple of 3 elements"
+        );
+        assert!(Vec::<f32>::from_value((1.0, "b").into_value()).is_err());
+
+        let power = |bits| Value::Number(2f32.powi(bits));
+        assert!(i32::from_value(power(31)).is_err());
+        assert!(u32::from_value(power(32)).is_err());
+        assert!(i64::from_value(power(63)).is_err());
+        assert_eq!(i32::from_value(power(30)), Ok(1 << 30));
+        assert_eq!(i32::from_value(Value::Number(-2f32.powi(31))), Ok(i32::MIN));
+    }
+
+    #[test]
+    fn test_host_functions() {
+        fn adapt<Args, F: HostFunction<Args>>(f: F) -> F {
+            f
+        }
+        let add = adapt(|a: f32, b: f32| a + b);
+        assert_eq!(add.arity(), 2);
+        assert_eq!(add.invoke("add", &[Value::Number(1.0), Value::Number(2.0)]).unwrap(), Value::Number(3.0));
+        let error = add.invoke("add", &[Value::Number(1.0), Value::Bool(true)]).unwrap_err();
+        assert_eq!(error.message, "Invalid argument 2 to 'add': expected number but got bool.");
+
+        let checked = adapt(|n: i32| if n < 0 { Err("negative") } else { Ok(n * 2) });
+        assert_eq!(checked.invoke("checked", &[Value::Number(-1.0)]).unwrap_err().message, "negative");
+        assert_eq!(adapt(|| "hi").arity(), 0);
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+
+use crate::error::ErrorKind;
+#[cfg(test)]
+use crate::codes;
+use crate::token::{Span, TAB_WIDTH};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// The script diagnostics point into: a display name (the path, or `<prompt>`) and its text.
+#[derive(Default)]
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+}
+
+/// A machine-applicable fix: replace the text at `span` with `replacement`. An empty span inserts it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub span: Span,
+    pub replacement: String,
+    /// Shown next to the span, e.g. "add ';' here".
+    pub message: String,
+}
+
+/// A message about a span of source, rendered rustc-style:
+///
+/// ```text
+/// error[E0301]: Cannot assign a value of type String to 'x' of type Number.
+///  --> script.lox:2:1
+///   |
+/// 1 | var x: Number = 1;
+///   |     - 'x' declared as Number here
+/// 2 | x = "a";
+///   | ^
+/// ```
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub kind: ErrorKind,
+    pub code: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    pub label: Option<String>,
+    pub secondary: Vec<(Span, String)>,
+    pub notes: Vec<String>,
+    /// A suggested fix, e.g. "did you mean 'print'?".
+    pub help: Option<String>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    pub fn new(kind: ErrorKind, code: &'static str, message: &str) -> Self {
+        Self {
+            kind,
+            code,
+            message: message.to_string(),
+            span: None,
+            label: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self.kind {
+            ErrorKind::Runtime => "runtime error",
+            _ => "error",
+        }
+    }
+
+    pub fn with_span(self, span: Span) -> Self {
+        Self { span: Some(span), ..self }
+    }
+
+    pub fn with_label(self, label: &str) -> Self {
+        Self { label: Some(label.to_string()), ..self }
+    }
+
+    pub fn with_secondary(mut self, span: Span, label: &str) -> Self {
+        self.secondary.push((span, label.to_string()));
+        self
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn with_help(self, help: &str) -> Self {
+        Self { help: Some(help.to_string()), ..self }
+    }
+
+    pub fn with_suggestion(mut self, span: Span, replacement: &str, message: &str) -> Self {
+        self.suggestions.push(Suggestion {
+            span,
+            replacement: replacement.to_string(),
+            message: message.to_string(),
+        });
+        self
+    }
+
+    pub fn render(&self, source: &SourceFile, color: bool) -> String {
+        let paint = |style: &str, text: &str| match color {
+            true => format!("{}{}{}", style, text, RESET),
+            false => text.to_string(),
+        };
+
+        let title = format!("{}[{}]", self.title(), self.code);
+        let mut out = format!("{}{}\n", paint(RED, &title), paint(BOLD, &format!(": {}", self.message)));
+
+        // Spans built without a scanner (line 0) or outside the source can't be shown. Unlike `lines`, splitting
+        // keeps the empty line after a final newline, where the end of input is.
+        let lines: Vec<&str> = source.text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).collect();
+        let shown = |span: &Span| span.start_line >= 1 && span.start_line <= lines.len();
+
+        let mut marks: BTreeMap<usize, Vec<(Span, char, &str, &str)>> = BTreeMap::new();
+        if let Some(span) = self.span.filter(shown) {
+            out += &format!(" {} {}:{}:{}\n", paint(BLUE, "-->"), source.name, span.start_line, span.start_column);
+            marks.entry(span.start_line).or_default().push((span, '^', self.label.as_deref().unwrap_or(""), RED));
+        } else if let Some(span) = self.span.filter(|span| span.start_line > 0) {
+            out += &format!(" {} line {}\n", paint(BLUE, "-->"), span.start_line);
+        }
+        for (span, label) in self.secondary.iter().filter(|(span, _)| shown(span)) {
+            marks.entry(span.start_line).or_default().push((*span, '-', label, BLUE));
+        }
+        let suggestions: Vec<(Span, String)> = self.suggestions.iter()
+            .filter(|suggestion| shown(&suggestion.span))
+            .map(|suggestion| (suggestion.span, format!("help: {}", suggestion.message)))
+            .collect();
+        for (span, label) in &suggestions {
+            marks.entry(span.start_line).or_default().push((*span, '+', label, BLUE));
+        }
+
+        let width = marks.keys().next_back().map_or(1, |line| line.to_string().len());
+        let gutter = |line: &str| paint(BLUE, &format!("{:>width$} |", line, width = width));
+        if !marks.is_empty() {
+            out += &format!("{}\n", gutter(""));
+        }
+        for (line, marks) in &marks {
+            let text = expand_tabs(lines[line - 1]);
+            out += &format!("{} {}\n", gutter(&line.to_string()), text);
+            for (span, mark, label, style) in marks {
+                let end = match span.end_line == span.start_line {
+                    true => span.end_column,
+                    false => text.chars().count() + 1, // Multi-line spans are underlined to the end of their first line
+                };
+                let underline = mark.to_string().repeat(end.saturating_sub(span.start_column).max(1));
+                let mut row = format!("{}{}", " ".repeat(span.start_column.saturating_sub(1)), underline);
+                if !label.is_empty() {
+                    row += &format!(" {}", label);
+                }
+                out += &format!("{} {}\n", gutter(""), paint(style, &row));
+            }
+        }
+
+        let notes = self.notes.iter().map(|note| format!("note: {}", note));
+        for note in notes.chain(self.help.iter().map(|help| format!("help: {}", help))) {
+            out += &format!("{} {}\n", paint(BLUE, &format!("{:>width$} =", "", width = width)), paint(BOLD, &note));
+        }
+        out
+    }
+}
+
+fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::new();
+    for c in line.chars() {
+        match c {
+            '\t' => {
+                let column = expanded.chars().count();
+                expanded += &" ".repeat(TAB_WIDTH - column % TAB_WIDTH);
+            }
+            _ => expanded.push(c),
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::scanner::scan_tokens;
+
+    use super::*;
+
+    fn source(text: &str) -> SourceFile {
+        SourceFile {
+            name: "test.lox".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let source = source("var x: Number = 1;\n\tx = \"a\";\n");
+        let tokens = scan_tokens(source.text.clone()).unwrap();
+        let diagnostic = Diagnostic::new(ErrorKind::Type, codes::MISMATCHED_TYPES, "Cannot assign a value of type String to 'x' of type Number.")
+            .with_span(tokens[7].span.to(tokens[9].span))
+            .with_secondary(tokens[1].span, "'x' declared as Number here")
+            .with_note("annotated variables keep their type")
+            .with_help("did you mean to declare a new variable?");
+
+        assert_eq!(diagnostic.render(&source, false), "\
+error[E0301]: Cannot assign a value of type String to 'x' of type Number.
+ --> test.lox:2:5
+  |
+1 | var x: Number = 1;
+  |     - 'x' declared as Number here
+2 |     x = \"a\";
+  |     ^^^^^^^
+  = note: annotated variables keep their type
+  = help: did you mean to declare a new variable?
+");
+    }
+
+    #[test]
+    fn test_render_at_end() {
+        let source = source("print a\n");
+        let (_, diagnostics) = Parser::new(scan_tokens(source.text.clone()).unwrap()).parse();
+        assert_eq!(diagnostics[0].render(&source, false), "\
+error[E0101]: Expect ';' after value.
+ --> test.lox:2:1
+  |
+1 | print a
+  |        + help: add ';' here
+2 | 
+  | ^ at end
+");
+    }
+
+    #[test]
+    fn test_render_without_source() {
+        let diagnostic = Diagnostic::new(ErrorKind::Runtime, codes::OPERAND_TYPE, "Operands must be numbers").with_label("here");
+        assert_eq!(diagnostic.render(&source(""), false), "runtime error[E0400]: Operands must be numbers\n");
+
+        let colored = Diagnostic::new(ErrorKind::Syntax, codes::EXPECTED_EXPRESSION, "Expect expression.").render(&source(""), true);
+        assert!(colored.starts_with(RED));
+    }
+}
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
-use crate::error::Error;
-use crate::error::Error::RuntimeError;
+use crate::codes;
+use crate::error::{Error, ErrorKind};
 use crate::interpreter::Value;
-use crate::token::Token;
+use crate::suggest;
+use crate::token::{Span, Token};
+
+/// The keywords that are values, and so can stand where a misspelled variable did.
+const VALUE_KEYWORDS: [&str; 3] = ["true", "false", "nil"];
 
 #[derive(Debug, Default, Clone)]
 pub struct Environment {
@@ -31,27 +35,54 @@ impl Environment {
     }
 
     pub fn get(&self, name: &Token) -> Result<Value, Error> {
-        match self.values.get(&name.lexeme) {
-            Some(value) => Ok(value.clone()),
-            None => match &self.enclosing {
-                Some(parent_env) => { parent_env.get(name) }
-                None => { Err(RuntimeError(Option::from(format!("Undefined variable '{}'.", name.lexeme)))) }
-            },
+        self.find(&name.lexeme).cloned().ok_or_else(|| self.undefined(&name.lexeme, Some(name.span), true))
+    }
+
+    /// Checks that `name` is defined, failing like `assign` would.
+    pub(crate) fn resolve(&self, name: &Token) -> Result<(), Error> {
+        match self.find(&name.lexeme) {
+            Some(_) => Ok(()),
+            None => Err(self.undefined(&name.lexeme, Some(name.span), false)),
         }
     }
 
     pub(crate) fn assign(&mut self, name: &Token, value: Value) -> Result<Value, Error> {
-        match self.values.contains_key(&name.lexeme) {
-            true => {
-                self.values.insert(name.lexeme.clone(), value.clone());
+        match self.find_mut(&name.lexeme) {
+            Some(slot) => {
+                *slot = value.clone();
                 Ok(value)
             }
-            false => {
-                match &mut self.enclosing {
-                    Some(parent_env) => { parent_env.assign(name, value) }
-                    None => { Err(RuntimeError(Option::from(format!("Undefined variable '{}'.", name.lexeme)))) }
-                }
-            }
+            None => Err(self.undefined(&name.lexeme, Some(name.span), false)),
         }
     }
-}
\ No newline at end of file
+
+    fn find(&self, name: &str) -> Option<&Value> {
+        match self.values.get(name) {
+            Some(value) => Some(value),
+            None => self.enclosing.as_ref().and_then(|parent_env| parent_env.find(name)),
+        }
+    }
+
+    fn find_mut(&mut self, name: &str) -> Option<&mut Value> {
+        match self.values.get_mut(name) {
+            Some(value) => Some(value),
+            None => self.enclosing.as_mut().and_then(|parent_env| parent_env.find_mut(name)),
+        }
+    }
+
+    /// Reports `name` as undefined, suggesting the closest visible variable. Where `name` is read as a value, the
+    /// value keywords are candidates too; `true = 1` would be no fix for an assignment.
+    pub(crate) fn undefined(&self, name: &str, span: Option<Span>, read: bool) -> Error {
+        let error = Error::new(ErrorKind::Resolution, codes::UNDEFINED_VARIABLE, &format!("Undefined variable '{}'.", name), span);
+        let mut visible = if read { VALUE_KEYWORDS.to_vec() } else { Vec::new() };
+        let mut scope = Some(self);
+        while let Some(environment) = scope {
+            visible.extend(environment.values.keys().map(String::as_str));
+            scope = environment.enclosing.as_deref();
+        }
+        match suggest::closest(name, visible) {
+            Some(candidate) => error.with_help(&format!("did you mean '{}'?", candidate)),
+            None => error,
+        }
+    }
+}
@@ -1,35 +1,180 @@
-use crate::HAD_ERROR;
-use crate::token::{Token, TokenType};
+use std::error;
+use std::fmt;
 
+use crate::diagnostic::Diagnostic;
+use crate::token::Span;
+
+/// The stage an error comes from, for embedders to match on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Malformed tokens: bad characters, unterminated strings and comments, malformed numbers.
+    Lexical,
+    /// Source that doesn't follow the grammar.
+    Syntax,
+    /// Variables that aren't defined where they are used.
+    Resolution,
+    /// Unknown type names and values whose static types don't fit, found before the program runs.
+    Type,
+    /// Everything that goes wrong while the program runs.
+    Runtime,
+    /// Reading a script or writing output failed.
+    Io,
+}
+
+impl ErrorKind {
+    /// The stage's name, e.g. "syntax". Not to be confused with `Error::code`, the stable `E0xxx` code.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::Lexical => "lexical",
+            ErrorKind::Syntax => "syntax",
+            ErrorKind::Resolution => "resolution",
+            ErrorKind::Type => "type",
+            ErrorKind::Runtime => "runtime",
+            ErrorKind::Io => "io",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An error from any stage of running Lox source, with where it happened and what caused it.
 #[derive(Debug)]
-pub enum Error {
-    ParseError(Option<String>),
-    RuntimeError(Option<String>),
+pub struct Error {
+    pub kind: ErrorKind,
+    /// The stable code `rlox explain` describes, e.g. `E0200`.
+    pub code: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    // Boxed so that results carrying an error stay small; most errors have neither.
+    details: Option<Box<Details>>,
 }
 
-pub fn error(line: usize, message: &str) {
-    report(line, "", message);
+#[derive(Debug, Default)]
+struct Details {
+    help: Option<String>,
+    cause: Option<Box<dyn error::Error + Send + Sync>>,
 }
 
-pub fn runtime_error(error: Error) {
-    if let Error::RuntimeError(message) = error {
-        if let Some(ref msg) = message {
-            eprintln!("{}", msg);
-        } else {
-            eprintln!("Runtime error occurred");
+impl Error {
+    pub fn new(kind: ErrorKind, code: &'static str, message: &str, span: Option<Span>) -> Self {
+        Self {
+            kind,
+            code,
+            message: message.to_string(),
+            span,
+            details: None,
+        }
+    }
+
+    pub fn syntax(code: &'static str, message: &str, span: Span) -> Self {
+        Error::new(ErrorKind::Syntax, code, message, Some(span))
+    }
+
+    pub fn resolution(code: &'static str, message: &str, span: Span) -> Self {
+        Error::new(ErrorKind::Resolution, code, message, Some(span))
+    }
+
+    pub fn runtime(code: &'static str, message: &str, span: Span) -> Self {
+        Error::new(ErrorKind::Runtime, code, message, Some(span))
+    }
+
+    /// A runtime error without a location yet, e.g. one raised by a call made from Rust.
+    pub fn unlocated(code: &'static str, message: &str) -> Self {
+        Error::new(ErrorKind::Runtime, code, message, None)
+    }
+
+    pub fn with_cause(mut self, cause: impl error::Error + Send + Sync + 'static) -> Self {
+        self.details.get_or_insert_with(Box::default).cause = Some(Box::new(cause));
+        self
+    }
+
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.details.get_or_insert_with(Box::default).help = Some(help.to_string());
+        self
+    }
+
+    /// A suggested fix, e.g. "did you mean 'count'?".
+    pub fn help(&self) -> Option<&str> {
+        self.details.as_ref().and_then(|details| details.help.as_deref())
+    }
+
+    /// Places an error that has no location yet at `span`.
+    pub fn or_at(self, span: Span) -> Self {
+        Self { span: self.span.or(Some(span)), ..self }
+    }
+
+    /// Describes the first of a stage's diagnostics; the rest have been reported alongside it.
+    pub fn from_diagnostic(diagnostic: &Diagnostic) -> Self {
+        let error = Error::new(diagnostic.kind, diagnostic.code, &diagnostic.message, diagnostic.span);
+        match &diagnostic.help {
+            Some(help) => error.with_help(help),
+            None => error,
+        }
+    }
+
+    /// Renders the error like any other diagnostic, with each cause in the chain as a note.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let mut diagnostic = Diagnostic::new(self.kind, self.code, &self.message);
+        if let Some(span) = self.span {
+            diagnostic = diagnostic.with_span(span);
+        }
+        let mut cause = error::Error::source(self);
+        while let Some(error) = cause {
+            diagnostic = diagnostic.with_note(&format!("caused by: {}", error));
+            cause = error.source();
+        }
+        match self.help() {
+            Some(help) => diagnostic.with_help(help),
+            None => diagnostic,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span.filter(|span| span.start_line > 0) {
+            Some(span) => write!(
+                f,
+                "{} error[{}] at {}:{}: {}",
+                self.kind, self.code, span.start_line, span.start_column, self.message
+            ),
+            None => write!(f, "{} error[{}]: {}", self.kind, self.code, self.message),
         }
     }
-    *HAD_ERROR.lock().unwrap() = true;
 }
 
-pub fn error_tok(token: &Token, message: &str) {
-    match token.token_type {
-        TokenType::Eof => report(token.line, " at end", message),
-        _ => report(token.line, &format!(" at '{}'", token.lexeme), message),
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        let cause = self.details.as_ref()?.cause.as_deref()?;
+        Some(cause as &(dyn error::Error + 'static))
     }
 }
 
-pub fn report(line: usize, location: &str, message: &str) {
-    eprintln!("[line {}] Error {}: {}", line, location, message);
-    *HAD_ERROR.lock().unwrap() = true;
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use crate::codes;
+
+    use super::*;
+
+    #[test]
+    fn test_display_and_cause() {
+        let span = Span { start_line: 2, start_column: 5, ..Span::default() };
+        let error = Error::runtime(codes::CANNOT_WRITE, "Cannot write output.", span)
+            .with_cause(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"));
+        assert_eq!(error.to_string(), "runtime error[E0501] at 2:5: Cannot write output.");
+        assert_eq!(error.source().unwrap().to_string(), "pipe closed");
+        assert_eq!(error.to_diagnostic().notes, vec!["caused by: pipe closed".to_string()]);
+
+        let error = Error::unlocated(codes::UNDEFINED_VARIABLE, "Undefined variable 'f'.").or_at(span);
+        assert_eq!(error.span, Some(span));
+        let error = Error::new(ErrorKind::Io, codes::CANNOT_READ, "Cannot read file.", None);
+        assert_eq!(error.to_string(), "io error[E0500]: Cannot read file.");
+    }
 }
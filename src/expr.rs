@@ -1,4 +1,4 @@
-use crate::token::Token;
+use crate::token::{Span, Token};
 
 /**
 expression     → literal
@@ -8,18 +8,76 @@ expression     → literal
 
 literal        → NUMBER | STRING | "true" | "false" | "nil" ;
 grouping       → "(" expression ")" ;
+tuple          → "(" expression "," ( expression ( "," expression )* ","? )? ")" ;
 unary          → ( "-" | "!" ) expression ;
+call           → expression "(" ( expression ( "," expression )* )? ")" ;
 binary         → expression operator expression ;
-operator       → "==" | "!=" | "<" | "<=" | ">" | ">="
+range          → expression ( ".." | "..=" ) expression ( "step" expression )? ;
+operator       → "==" | "!=" | "<" | "<=" | ">" | ">=" | "in"
                | "+"  | "-"  | "*" | "/" ;
  **/
 pub enum Expr {
     Assign(Token, Box<Expr>),
+    AssignPattern(Pattern, Box<Expr>),
     Binary(Box<Expr>, Token, Box<Expr>),
-    Grouping(Box<Expr>),
-    LiteralNum(Option<f32>),
-    LiteralStr(Option<String>),
-    LiteralBool(Option<bool>),
+    Call(Box<Expr>, Token, Vec<Expr>),
+    Grouping(Box<Expr>, Span),
+    LiteralNum(Option<f32>, Span),
+    LiteralStr(Option<String>, Span),
+    LiteralBool(Option<bool>, Span),
+    Range(Box<Expr>, Token, Box<Expr>, Option<Box<Expr>>),
+    Tuple(Vec<Expr>, Span),
     Unary(Token, Box<Expr>),
     Variable(Token),
 }
+
+impl Expr {
+    /// The source text this expression was parsed from, including any parentheses.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Assign(name, value) => name.span.to(value.span()),
+            Expr::AssignPattern(pattern, value) => pattern.first_token().span.to(value.span()),
+            Expr::Binary(left, _, right) => left.span().to(right.span()),
+            Expr::Call(callee, paren, _) => callee.span().to(paren.span),
+            Expr::Grouping(_, span)
+            | Expr::LiteralNum(_, span)
+            | Expr::LiteralStr(_, span)
+            | Expr::LiteralBool(_, span)
+            | Expr::Tuple(_, span) => *span,
+            Expr::Range(start, _, end, step) => match step {
+                Some(step) => start.span().to(step.span()),
+                None => start.span().to(end.span()),
+            },
+            Expr::Unary(op, right) => op.span.to(right.span()),
+            Expr::Variable(name) => name.span,
+        }
+    }
+}
+
+/**
+pattern        → IDENTIFIER
+               | IDENTIFIER "(" ( pattern ( "," pattern )* )? ")"
+               | "(" pattern ( "," pattern )* ")" ;
+ **/
+pub enum Pattern {
+    Name(Token),
+    Tuple(Vec<Pattern>),
+    Variant(Token, Vec<Pattern>),
+}
+
+impl Pattern {
+    pub fn first_token(&self) -> &Token {
+        match self {
+            Pattern::Name(name) | Pattern::Variant(name, _) => name,
+            Pattern::Tuple(elements) => elements[0].first_token(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Name(name) => name.span,
+            Pattern::Variant(name, fields) => fields.last().map_or(name.span, |field| name.span.to(field.span())),
+            Pattern::Tuple(elements) => elements[0].span().to(elements[elements.len() - 1].span()),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::diagnostic::{Diagnostic, Suggestion};
+use crate::parser::Parser;
+use crate::scanner;
+
+/// Fixing one mistake can uncover another that recovery skipped, so fixes are applied in passes.
+const MAX_PASSES: usize = 8;
+
+/// Applies the parser's suggestions to `source` until none are left, e.g. adding missing ';'s.
+/// Returns the fixed source and the number of fixes applied. Unless the fixes leave source that parses cleanly,
+/// they are all dropped and `source` comes back unchanged with a count of 0, so a wrong guess never reaches a file.
+pub fn fix(source: &str) -> (String, usize) {
+    let mut fixed = source.to_string();
+    let mut applied = 0;
+    for _ in 0..MAX_PASSES {
+        let Some(diagnostics) = parse(&fixed) else {
+            break;
+        };
+        if diagnostics.is_empty() {
+            return (fixed, applied);
+        }
+        let suggestions: Vec<Suggestion> = diagnostics.into_iter().flat_map(|diagnostic| diagnostic.suggestions).collect();
+        let (next, count) = apply(&fixed, &suggestions);
+        if count == 0 {
+            break;
+        }
+        fixed = next;
+        applied += count;
+    }
+    match parse(&fixed) {
+        Some(diagnostics) if diagnostics.is_empty() => (fixed, applied),
+        _ => (source.to_string(), 0),
+    }
+}
+
+/// The syntax errors in `source`, or `None` if it doesn't even scan.
+fn parse(source: &str) -> Option<Vec<Diagnostic>> {
+    let tokens = scanner::scan_tokens(source.to_string()).ok()?;
+    Some(Parser::new(tokens).parse().1)
+}
+
+/// Replaces the text of each suggestion's span. Suggestions overlapping one already applied are skipped.
+pub fn apply(text: &str, suggestions: &[Suggestion]) -> (String, usize) {
+    let mut suggestions: Vec<&Suggestion> = suggestions.iter().collect();
+    suggestions.sort_by_key(|suggestion| (suggestion.span.start, suggestion.span.end));
+
+    let mut fixed = String::new();
+    let mut copied = 0;
+    let mut last_start = None;
+    let mut applied = 0;
+    for suggestion in suggestions {
+        let span = suggestion.span;
+        // Two edits at one place can't be ordered safely; the next pass reports the second again.
+        if span.start < copied || last_start == Some(span.start) || span.end > text.len() {
+            continue;
+        }
+        fixed += &text[copied..span.start];
+        fixed += &suggestion.replacement;
+        copied = span.end;
+        last_start = Some(span.start);
+        applied += 1;
+    }
+    fixed += &text[copied..];
+    (fixed, applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::token::Span;
+
+    use super::*;
+
+    #[test]
+    fn test_fix() {
+        assert_eq!(fix("var a = 1\nprint (a + 2;\nprint a"), ("var a = 1;\nprint (a + 2);\nprint a;".to_string(), 3));
+        assert_eq!(fix("print 1\nvar a = 2"), ("print 1;\nvar a = 2;".to_string(), 2));
+        assert_eq!(fix("print f(1\nfor (i in 0..2 print i;"), ("print f(1);\nfor (i in 0..2) print i;".to_string(), 3));
+        assert_eq!(fix("print (1 + 2"), ("print (1 + 2);".to_string(), 2));
+        assert_eq!(fix("print 1;"), ("print 1;".to_string(), 0));
+        // Nothing to insert can fix a missing expression.
+        assert_eq!(fix("var a = ;"), ("var a = ;".to_string(), 0));
+        // These are missing operators, not terminators; guessing would mangle them.
+        assert_eq!(fix("print 1 2 3 4;"), ("print 1 2 3 4;".to_string(), 0));
+        assert_eq!(fix("print (1 + 2 3);"), ("print (1 + 2 3);".to_string(), 0));
+        // A fix that leaves other syntax errors behind is dropped rather than half applied.
+        assert_eq!(fix("print 1\nvar = 2;"), ("print 1\nvar = 2;".to_string(), 0));
+    }
+
+    #[test]
+    fn test_apply() {
+        let at = |start, end| Span { start, end, ..Span::default() };
+        let suggestion = |span, replacement: &str| Suggestion {
+            span,
+            replacement: replacement.to_string(),
+            message: String::new(),
+        };
+        let suggestions = [suggestion(at(5, 5), "!"), suggestion(at(0, 1), "H"), suggestion(at(5, 5), "?")];
+        assert_eq!(apply("hello world", &suggestions), ("Hello! world".to_string(), 2));
+    }
+}
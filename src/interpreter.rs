@@ -1,50 +1,168 @@
+use std::any::Any;
+
+use crate::codes;
 use crate::environment::Environment;
-use crate::error::{Error, runtime_error};
-use crate::error::Error::RuntimeError;
-use crate::expr::Expr;
+use crate::error::{Error, ErrorKind};
+use crate::expr::{Expr, Pattern};
 use crate::interpreter::Value::Nil;
+use crate::native::NativeFunction;
+use crate::range::{Range, RangeIter};
+use crate::sink::{OutputSink, StdoutSink};
 use crate::stmt::Stmt;
-use crate::token::TokenType;
+use crate::token::{Span, Token, TokenType};
+use crate::variant::{Variant, VariantConstructor};
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Value { // In java version the return type is Object
     Number(f32),
     String(String),
     Bool(bool),
+    Range(Range),
+    Tuple(Vec<Value>),
+    Variant(Variant),
+    Constructor(VariantConstructor),
+    Native(NativeFunction),
     Nil,
 }
 
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Bool(_) => "bool",
+            Value::Range(_) => "range",
+            Value::Tuple(_) => "tuple",
+            Value::Variant(_) => "enum",
+            Value::Constructor(_) => "constructor",
+            Value::Native(_) => "function",
+            Value::Nil => "nil",
+        }
+    }
+}
+
+/// Iteration protocol behind `for (x in iterable)`: yields the elements of an iterable `Value` lazily.
+pub enum ValueIter {
+    /// The string and the byte offset of the next char, so chars are decoded one at a time.
+    Chars(String, usize),
+    Range(RangeIter),
+    Values(std::vec::IntoIter<Value>),
+}
+
+impl Iterator for ValueIter {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        match self {
+            ValueIter::Chars(text, offset) => {
+                let c = text[*offset..].chars().next()?;
+                *offset += c.len_utf8();
+                Some(Value::String(c.to_string()))
+            }
+            ValueIter::Range(range) => range.next().map(Value::Number),
+            ValueIter::Values(values) => values.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            ValueIter::Chars(text, offset) => {
+                let remaining = text.len() - offset;
+                (remaining.div_ceil(4), Some(remaining))
+            }
+            ValueIter::Range(range) => range.size_hint(),
+            ValueIter::Values(values) => values.size_hint(),
+        }
+    }
+}
+
 pub struct Interpreter {
     environment: Environment,
+    output: Box<dyn OutputSink>,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
         Self {
-            environment: Environment::new()
+            environment: Environment::new(),
+            output: Box::new(StdoutSink),
+        }
+    }
+
+    pub fn set_output(&mut self, output: impl OutputSink + 'static) {
+        self.output = Box::new(output);
+    }
+
+    /// Takes the output sink back if it is an `O`, leaving stdout in its place.
+    pub fn take_output<O: OutputSink>(&mut self) -> Option<O> {
+        if !(self.output.as_ref() as &dyn Any).is::<O>() {
+            return None;
         }
+        let output: Box<dyn OutputSink> = std::mem::replace(&mut self.output, Box::new(StdoutSink));
+        (output as Box<dyn Any>).downcast().ok().map(|output| *output)
     }
 
-    pub fn interpret(&mut self, statements: Vec<Stmt>) {
+    /// Runs each statement in turn, handing runtime errors to `report` as they happen.
+    /// Errors without a location of their own are placed at the failing statement.
+    /// Returns the value of the last statement, which is nil unless it is an expression statement.
+    pub fn interpret(&mut self, statements: &[Stmt], mut report: impl FnMut(Error)) -> Value {
+        let mut last = Nil;
         for stmt in statements {
-            match self.interpret_stmt(stmt) {
-                Ok(_) => {}
-                Err(e) => runtime_error(e),
+            last = match self.interpret_stmt(stmt) {
+                Ok(value) => value,
+                Err(e) => {
+                    report(e.or_at(stmt.span()));
+                    Nil
+                }
             }
         }
+        last
     }
 
-    fn interpret_stmt(&mut self, stmt: Stmt) -> Result<Value, Error> {
-        match stmt {
-            Stmt::Expression(expr) => self.evaluate(expr),
-            Stmt::Print(expr) => {
-                let result = self.evaluate(expr);
-                if let Ok(ref val) = result {
-                    println!("{}", self.stringify(val));
+    /// Looks up a global variable, as seen between runs.
+    pub fn global(&self, name: &str) -> Option<Value> {
+        self.environment.values.get(name).cloned()
+    }
+
+    /// The error for a global callable that isn't defined, suggesting a close match.
+    pub fn undefined_global(&self, name: &str) -> Error {
+        self.environment.undefined(name, None, false)
+    }
+
+    pub fn define_global(&mut self, name: &str, value: Value) {
+        self.environment.define(name.to_string(), value);
+    }
+
+    /// Calls a callable value. Errors carry no location; callers inside Lox add the call's.
+    pub fn call(&mut self, callee: Value, arguments: Vec<Value>) -> Result<Value, Error> {
+        match callee {
+            Value::Constructor(constructor) => {
+                if arguments.len() != constructor.arity() {
+                    return Err(Error::unlocated(codes::ARITY_MISMATCH, &format!("Expected {} arguments but got {}.", constructor.arity(), arguments.len())));
+                }
+                Ok(Value::Variant(constructor.construct(arguments)))
+            }
+            Value::Native(native) => {
+                if arguments.len() != native.arity {
+                    return Err(Error::unlocated(codes::ARITY_MISMATCH, &format!("Expected {} arguments but got {}.", native.arity, arguments.len())));
                 }
-                result
+                native.call(&arguments)
+            }
+            _ => Err(Error::unlocated(codes::CALL_NON_CALLABLE, &format!("Value of type {} is not callable.", callee.type_name()))),
+        }
+    }
+
+    fn interpret_stmt(&mut self, stmt: &Stmt) -> Result<Value, Error> {
+        match stmt {
+            Stmt::Expression(expr, _) => self.evaluate(expr),
+            Stmt::Print(expr, _) => {
+                let val = self.evaluate(expr)?;
+                let line = self.stringify(&val);
+                self.output.print(&line)
+                    .map_err(|e| Error::new(ErrorKind::Io, codes::CANNOT_WRITE, "Cannot write output.", Some(stmt.span())).with_cause(e))?;
+                Ok(val)
             }
-            Stmt::Var(name, initializer) => {
+            Stmt::Var(name, _, initializer, _) => {
                 match initializer {
                     Some(expr) => {
                         let value = self.evaluate(expr)?;
@@ -57,99 +175,263 @@ impl Interpreter {
                     }
                 }
             }
-            Stmt::Block(stmts) => {
-                self.environment = Environment::new_enclosing(self.environment.clone());
-                for stmt in stmts {
-                    self.interpret_stmt(stmt)?;
+            Stmt::VarPattern(pattern, initializer, _) => {
+                let value = self.evaluate(initializer)?;
+                for (name, value) in self.destructure(pattern, value)? {
+                    self.environment.define(name.lexeme.clone(), value);
                 }
-                if let Some(enclosing) = self.environment.enclosing.clone() {
-                    self.environment = *enclosing
-                } else {
-                    // TODO: how to do this without a runtime check?
-                    panic!("impossible");
+                Ok(Value::Nil)
+            }
+            Stmt::Enum(name, variants, _) => {
+                for (variant, fields) in variants {
+                    let value = match fields {
+                        Some(fields) => Value::Constructor(VariantConstructor {
+                            enum_name: name.lexeme.clone(),
+                            name: variant.lexeme.clone(),
+                            params: fields.iter().map(|field| field.lexeme.clone()).collect(),
+                        }),
+                        None => Value::Variant(Variant {
+                            enum_name: name.lexeme.clone(),
+                            name: variant.lexeme.clone(),
+                            fields: Vec::new(),
+                        }),
+                    };
+                    self.environment.define(variant.lexeme.clone(), value);
+                }
+                Ok(Value::Nil)
+            }
+            Stmt::Block(stmts, _) => {
+                self.in_scope(|interpreter| {
+                    for stmt in stmts {
+                        interpreter.interpret_stmt(stmt)?;
+                    }
+                    Ok(Nil)
+                })
+            }
+            Stmt::ForIn(pattern, iterable, body, _) => {
+                let span = iterable.span();
+                let iterable = self.evaluate(iterable)?;
+                for element in self.iterate(iterable, span)? {
+                    self.in_scope(|interpreter| {
+                        for (name, value) in interpreter.destructure(pattern, element)? {
+                            interpreter.environment.define(name.lexeme.clone(), value);
+                        }
+                        interpreter.interpret_stmt(body)
+                    })?;
                 }
                 Ok(Nil)
             }
         }
     }
 
+    /// Runs `f` in a fresh scope nested in the current one, restoring the current scope even on error.
+    fn in_scope<F>(&mut self, f: F) -> Result<Value, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<Value, Error>,
+    {
+        self.environment = Environment::new_enclosing(std::mem::take(&mut self.environment));
+        let result = f(self);
+        if let Some(enclosing) = self.environment.enclosing.take() {
+            self.environment = *enclosing;
+        }
+        result
+    }
+
+    fn iterate(&self, iterable: Value, span: Span) -> Result<ValueIter, Error> {
+        match iterable {
+            Value::String(s) => Ok(ValueIter::Chars(s, 0)),
+            Value::Range(range) => Ok(ValueIter::Range(range.iter())),
+            Value::Tuple(values) => Ok(ValueIter::Values(values.into_iter())),
+            _ => Err(Error::runtime(codes::ITERATE_NON_ITERABLE, &format!("Value of type {} is not iterable.", iterable.type_name()), span)),
+        }
+    }
+
+    /// Matches `value` against `pattern`, returning the bindings in pattern order.
+    fn destructure<'p>(&self, pattern: &'p Pattern, value: Value) -> Result<Vec<(&'p Token, Value)>, Error> {
+        match (pattern, value) {
+            (Pattern::Name(name), value) => Ok(vec![(name, value)]),
+            (Pattern::Tuple(patterns), Value::Tuple(values)) => {
+                if patterns.len() != values.len() {
+                    return Err(Error::runtime(codes::DESTRUCTURE_MISMATCH, &format!("Cannot destructure a tuple of {} elements into {} variables.", values.len(), patterns.len()), pattern.span()));
+                }
+                let mut bindings = Vec::new();
+                for (pattern, value) in patterns.iter().zip(values) {
+                    bindings.extend(self.destructure(pattern, value)?);
+                }
+                Ok(bindings)
+            }
+            (Pattern::Tuple(patterns), value) => {
+                Err(Error::runtime(codes::DESTRUCTURE_MISMATCH, &format!("Cannot destructure a value of type {} into {} variables.", value.type_name(), patterns.len()), pattern.span()))
+            }
+            (Pattern::Variant(name, patterns), Value::Variant(variant)) => {
+                // The pattern names the variant currently bound to `name`, which fixes its enum too.
+                let enum_name = match self.environment.get(name)? {
+                    Value::Constructor(constructor) => constructor.enum_name,
+                    Value::Variant(variant) => variant.enum_name,
+                    other => return Err(Error::runtime(codes::DESTRUCTURE_MISMATCH, &format!("Pattern {} names a {}, not an enum variant.", name.lexeme, other.type_name()), name.span)),
+                };
+                if name.lexeme != variant.name || enum_name != variant.enum_name {
+                    return Err(Error::runtime(codes::DESTRUCTURE_MISMATCH, &format!("Cannot destructure variant {}.{} with pattern {}.{}.", variant.enum_name, variant.name, enum_name, name.lexeme), name.span));
+                }
+                if patterns.len() != variant.fields.len() {
+                    return Err(Error::runtime(codes::DESTRUCTURE_MISMATCH, &format!("Cannot destructure {} with {} fields into {} variables.", variant.name, variant.fields.len(), patterns.len()), pattern.span()));
+                }
+                let mut bindings = Vec::new();
+                for (pattern, value) in patterns.iter().zip(variant.fields) {
+                    bindings.extend(self.destructure(pattern, value)?);
+                }
+                Ok(bindings)
+            }
+            (Pattern::Variant(name, _), value) => {
+                Err(Error::runtime(codes::DESTRUCTURE_MISMATCH, &format!("Cannot destructure a value of type {} with pattern {}.", value.type_name(), name.lexeme), pattern.span()))
+            }
+        }
+    }
+
     fn stringify(&self, val: &Value) -> String {
         match val {
             Value::Number(n) => n.to_string(),
             Value::String(s) => s.to_string(),
             Value::Bool(b) => b.to_string(),
+            Value::Range(range) => range.to_string(),
+            Value::Tuple(values) => {
+                let values: Vec<String> = values.iter().map(|value| self.stringify(value)).collect();
+                match values.len() {
+                    1 => format!("({},)", values[0]),
+                    _ => format!("({})", values.join(", ")),
+                }
+            }
+            Value::Variant(variant) if variant.fields.is_empty() => variant.name.clone(),
+            Value::Variant(variant) => {
+                let fields: Vec<String> = variant.fields.iter().map(|field| self.stringify(field)).collect();
+                format!("{}({})", variant.name, fields.join(", "))
+            }
+            Value::Constructor(constructor) => format!("<constructor {}>", constructor.name),
+            Value::Native(native) => format!("<native fn {}>", native.name),
             Value::Nil => "nil".to_string(),
         }
     }
 
-    fn evaluate(&mut self, expr: Expr) -> Result<Value, Error> {
+    fn evaluate(&mut self, expr: &Expr) -> Result<Value, Error> {
         match expr {
-            Expr::LiteralNum(num) => Ok(Value::Number(num.unwrap())),
-            Expr::LiteralStr(s) => Ok(Value::String(s.unwrap())),
-            Expr::LiteralBool(b) => Ok(Value::Bool(b.unwrap())),
-            Expr::Grouping(expr) => self.evaluate(*expr),
+            Expr::LiteralNum(num, _) => Ok(Value::Number(num.unwrap())),
+            Expr::LiteralStr(Some(s), _) => Ok(Value::String(s.clone())),
+            Expr::LiteralStr(None, _) => Ok(Value::Nil),
+            Expr::LiteralBool(b, _) => Ok(Value::Bool(b.unwrap())),
+            Expr::Grouping(expr, _) => self.evaluate(expr),
+            Expr::Call(callee, _, arguments) => {
+                let callee = self.evaluate(callee)?;
+                let arguments = arguments.iter()
+                    .map(|argument| self.evaluate(argument))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(callee, arguments).map_err(|error| error.or_at(expr.span()))
+            }
+            Expr::Tuple(elements, _) => {
+                let values = elements.iter()
+                    .map(|element| self.evaluate(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Value::Tuple(values))
+            }
+            Expr::Range(start, op, end, step_expr) => {
+                let (start, end) = match (self.evaluate(start)?, self.evaluate(end)?) {
+                    (Value::Number(start), Value::Number(end)) => (start, end),
+                    _ => return Err(Error::runtime(codes::OPERAND_TYPE, "Range bounds must be numbers", op.span)),
+                };
+                let range = Range::new(start, end, op.token_type == TokenType::DotDotEqual)
+                    .ok_or_else(|| Error::runtime(codes::NON_FINITE_RANGE, "Range bounds must be finite", op.span))?;
+                match step_expr {
+                    Some(step_expr) => match self.evaluate(step_expr)? {
+                        Value::Number(step) if step.is_nan() => {
+                            Err(Error::runtime(codes::NON_FINITE_RANGE, "Range step must not be NaN", step_expr.span()))
+                        }
+                        Value::Number(step) => match range.step_by(step) {
+                            Some(range) => Ok(Value::Range(range)),
+                            None => Err(Error::runtime(codes::ZERO_STEP, "Range step must not be zero", step_expr.span())),
+                        },
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Range step must be a number", step_expr.span())),
+                    },
+                    None => Ok(Value::Range(range)),
+                }
+            }
             Expr::Unary(op, expr) => {
-                let right = self.evaluate(*expr)?;
+                let right = self.evaluate(expr)?;
                 match op.token_type {
                     TokenType::Minus => match right {
                         Value::Number(n) => Ok(Value::Number(-n)),
-                        _ => Err(RuntimeError(Option::from("Operand must be a number".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operand must be a number", op.span)),
                     },
                     TokenType::Bang => match right {
                         Value::Bool(b) => Ok(Value::Bool(!b)),
-                        _ => Err(RuntimeError(Option::from("Operand must be a boolean".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operand must be a boolean", op.span)),
                     },
-                    _ => Err(RuntimeError(Option::from("Invalid unary operator".to_string()))),
+                    _ => Err(Error::runtime(codes::OPERAND_TYPE, "Invalid unary operator", op.span)),
                 }
             }
             Expr::Binary(left, op, right) => {
-                let left = self.evaluate(*left)?;
-                let right = self.evaluate(*right)?;
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
                 match op.token_type {
                     TokenType::Minus => match (left, right) {
                         (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l - r)),
-                        _ => Err(RuntimeError(Option::from("Operands must be numbers".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be numbers", op.span)),
                     },
                     TokenType::Slash => match (left, right) {
                         (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l / r)),
-                        _ => Err(RuntimeError(Option::from("Operands must be numbers".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be numbers", op.span)),
                     },
                     TokenType::Star => match (left, right) {
                         (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l * r)),
-                        _ => Err(RuntimeError(Option::from("Operands must be numbers".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be numbers", op.span)),
                     },
                     TokenType::Plus => match (left, right) {
                         (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l + r)),
                         (Value::String(l), Value::String(r)) => Ok(Value::String(format!("{}{}", l, r))),
-                        _ => Err(RuntimeError(Option::from("Operands must be both numbers or strings".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be both numbers or strings", op.span)),
                     },
                     TokenType::Greater => match (left, right) {
                         (Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l > r)),
-                        _ => Err(RuntimeError(Option::from("Operands must be numbers".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be numbers", op.span)),
                     },
                     TokenType::GreaterEqual => match (left, right) {
                         (Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l >= r)),
-                        _ => Err(RuntimeError(Option::from("Operands must be numbers".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be numbers", op.span)),
                     },
                     TokenType::Less => match (left, right) {
                         (Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l < r)),
-                        _ => Err(RuntimeError(Option::from("Operands must be numbers".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be numbers", op.span)),
                     },
                     TokenType::LessEqual => match (left, right) {
                         (Value::Number(l), Value::Number(r)) => Ok(Value::Bool(l <= r)),
-                        _ => Err(RuntimeError(Option::from("Operands must be numbers".to_string()))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be numbers", op.span)),
+                    },
+                    TokenType::In => match (left, right) {
+                        (Value::Number(l), Value::Range(r)) => Ok(Value::Bool(r.contains(l))),
+                        (Value::String(l), Value::String(r)) => Ok(Value::Bool(r.contains(&l))),
+                        _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be a number and a range, or two strings", op.span)),
                     },
                     TokenType::BangEqual => Ok(Value::Bool(!self.equals(left, right))),
                     TokenType::EqualEqual => Ok(Value::Bool(self.equals(left, right))),
-                    _ => Err(RuntimeError(Option::from("Operands must be numbers".to_string()))),
+                    _ => Err(Error::runtime(codes::OPERAND_TYPE, "Operands must be numbers", op.span)),
                 }
             }
             Expr::Variable(identifier) => {
-                self.environment.get(&identifier)
+                self.environment.get(identifier)
             }
             Expr::Assign(name, value) => {
-                let value = self.evaluate(*value)?;
-                self.environment.assign(&name, value.clone())?;
+                let value = self.evaluate(value)?;
+                self.environment.assign(name, value.clone())?;
+                Ok(value)
+            }
+            Expr::AssignPattern(pattern, value) => {
+                let value = self.evaluate(value)?;
+                let bindings = self.destructure(pattern, value.clone())?;
+                // Resolve every name first so a bad one doesn't leave the others half assigned.
+                for (name, _) in &bindings {
+                    self.environment.resolve(name)?;
+                }
+                for (name, value) in bindings {
+                    self.environment.assign(name, value)?;
+                }
                 Ok(value)
             }
         }
@@ -160,6 +442,18 @@ impl Interpreter {
             (Value::Number(n1), Value::Number(n2)) => (n1 - n2).abs() < f32::EPSILON,
             (Value::String(s1), Value::String(s2)) => s1 == s2,
             (Value::Bool(b1), Value::Bool(b2)) => b1 == b2,
+            (Value::Range(r1), Value::Range(r2)) => r1 == r2,
+            (Value::Tuple(t1), Value::Tuple(t2)) => {
+                t1.len() == t2.len() && t1.into_iter().zip(t2).all(|(v1, v2)| self.equals(v1, v2))
+            }
+            (Value::Variant(v1), Value::Variant(v2)) => {
+                v1.enum_name == v2.enum_name
+                    && v1.name == v2.name
+                    && v1.fields.len() == v2.fields.len()
+                    && v1.fields.into_iter().zip(v2.fields).all(|(f1, f2)| self.equals(f1, f2))
+            }
+            (Value::Constructor(c1), Value::Constructor(c2)) => c1 == c2,
+            (Value::Native(n1), Value::Native(n2)) => n1 == n2,
             (Value::Nil, Value::Nil) => true,
             (_, _) => false,
         }
@@ -168,134 +462,253 @@ impl Interpreter {
 
 #[cfg(test)]
 mod tests {
-    use crate::token::Token;
+    use crate::parser::Parser;
+    use crate::scanner::scan_tokens;
+    use crate::token::Span;
 
     use super::*;
 
+    fn run(interpreter: &mut Interpreter, source: &str) -> Result<Value, Error> {
+        let tokens = scan_tokens(source.to_string()).map_err(|diagnostics| Error::from_diagnostic(&diagnostics[0]))?;
+        let (stmts, diagnostics) = Parser::new(tokens).parse();
+        if let Some(diagnostic) = diagnostics.first() {
+            return Err(Error::from_diagnostic(diagnostic));
+        }
+        for stmt in &stmts {
+            interpreter.interpret_stmt(stmt)?;
+        }
+        Ok(Nil)
+    }
+
+    fn get(interpreter: &Interpreter, name: &str) -> Value {
+        interpreter.environment.get(&Token::new(TokenType::Identifier, name, 1, None, None)).unwrap()
+    }
+
     #[test]
     fn test_evaluate() {
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::Plus, "+", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
         let mut interpreter = Interpreter::new();
 
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Number(3.0)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::Minus, "-", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Number(-1.0)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::Star, "*", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Number(2.0)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::Slash, "/", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Number(0.5)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::Greater, ">", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Bool(false)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::GreaterEqual, ">=", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Bool(false)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::Less, "<", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Bool(true)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::LessEqual, "<=", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Bool(true)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::BangEqual, "!=", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Bool(true)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralNum(Some(1.0))),
+            Box::new(Expr::LiteralNum(Some(1.0), Span::default())),
             Token::new(TokenType::EqualEqual, "==", 1, None, None),
-            Box::new(Expr::LiteralNum(Some(2.0))),
+            Box::new(Expr::LiteralNum(Some(2.0), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::Bool(false)),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let expr = Expr::Binary(
-            Box::new(Expr::LiteralStr(Some("Hello".to_string()))),
+            Box::new(Expr::LiteralStr(Some("Hello".to_string()), Span::default())),
             Token::new(TokenType::Plus, "+", 1, None, None),
-            Box::new(Expr::LiteralStr(Some(" World".to_string()))),
+            Box::new(Expr::LiteralStr(Some(" World".to_string()), Span::default())),
         );
-        match interpreter.evaluate(expr) {
+        match interpreter.evaluate(&expr) {
             Ok(val) => assert_eq!(val, Value::String("Hello World".to_string())),
             Err(e) => panic!("Error: {:?}", e)
         }
 
         let stmt = Stmt::Var(
             Token::new(TokenType::Identifier, "a", 1, None, None),
-            Some(Expr::LiteralNum(Some(2.0))),
+            None,
+            Some(Expr::LiteralNum(Some(2.0), Span::default())),
+            Span::default(),
         );
-        interpreter.interpret_stmt(stmt).unwrap();
+        interpreter.interpret_stmt(&stmt).unwrap();
 
-        let stmt = Stmt::Print(Expr::Variable(Token::new(TokenType::Identifier, "a", 1, None, None)));
-        match interpreter.interpret_stmt(stmt) {
+        let stmt = Stmt::Print(Expr::Variable(Token::new(TokenType::Identifier, "a", 1, None, None)), Span::default());
+        match interpreter.interpret_stmt(&stmt) {
             Ok(val) => assert_eq!(val, Value::Number(2.0)),
             Err(e) => panic!("Error: {:?}", e)
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_for_in() {
+        let mut interpreter = Interpreter::new();
+        run(&mut interpreter, "var s = \"\"; for (c in \"abc\") s = c + s;").unwrap();
+        assert_eq!(get(&interpreter, "s"), Value::String("cba".to_string()));
+        run(&mut interpreter, "var t = \"\"; for (c in \"héllo\") t = t + c + \"|\";").unwrap();
+        assert_eq!(get(&interpreter, "t"), Value::String("h|é|l|l|o|".to_string()));
+
+        assert!(run(&mut interpreter, "for (c in 1) print c;").is_err());
+        assert!(run(&mut interpreter, "for (k, v in \"ab\") print k;").is_err());
+        // The loop scope is unwound after an error.
+        assert!(run(&mut interpreter, "c;").is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        let mut interpreter = Interpreter::new();
+        run(&mut interpreter, "var n = 0; for (i in 1..=4) n = n + i;").unwrap();
+        assert_eq!(get(&interpreter, "n"), Value::Number(10.0));
+
+        run(&mut interpreter, "var s = \"\"; for (i in 3..0 step -1) s = s + \"x\";").unwrap();
+        assert_eq!(get(&interpreter, "s"), Value::String("xxx".to_string()));
+
+        run(&mut interpreter, "var a = 4 in 0..10 step 2; var b = 5 in 0..10 step 2; var c = 0..3 == 0..3;").unwrap();
+        assert_eq!(get(&interpreter, "a"), Value::Bool(true));
+        assert_eq!(get(&interpreter, "b"), Value::Bool(false));
+        assert_eq!(get(&interpreter, "c"), Value::Bool(true));
+
+        let code = |interpreter: &mut Interpreter, source| run(interpreter, source).unwrap_err().code;
+        assert_eq!(code(&mut interpreter, "var r = 0..3 step 0;"), codes::ZERO_STEP);
+        assert_eq!(code(&mut interpreter, "var z = 0; var r = 0..3 step z / z;"), codes::NON_FINITE_RANGE);
+        assert_eq!(code(&mut interpreter, "var z = 0; for (i in 0..=1 / z) print i;"), codes::NON_FINITE_RANGE);
+    }
+
+    #[test]
+    fn test_tuples() {
+        let mut interpreter = Interpreter::new();
+        run(&mut interpreter, "var (a, (b, c)) = (1, (2, 3)); (a, c) = (c, a);").unwrap();
+        assert_eq!(get(&interpreter, "a"), Value::Number(3.0));
+        assert_eq!(get(&interpreter, "b"), Value::Number(2.0));
+        assert_eq!(get(&interpreter, "c"), Value::Number(1.0));
+
+        run(&mut interpreter, "var same = (1, \"x\") == (1, \"x\"); var different = (1, 2) == (1, 2, 3);").unwrap();
+        assert_eq!(get(&interpreter, "same"), Value::Bool(true));
+        assert_eq!(get(&interpreter, "different"), Value::Bool(false));
+
+        run(&mut interpreter, "var (one,) = (1,); var (grouped) = 2;").unwrap();
+        assert_eq!(get(&interpreter, "one"), Value::Number(1.0));
+        assert_eq!(get(&interpreter, "grouped"), Value::Number(2.0));
+
+        run(&mut interpreter, "var n = 0; for (k, v in ((1, 2), (3, 4))) n = n + k * v;").unwrap();
+        assert_eq!(get(&interpreter, "n"), Value::Number(14.0));
+
+        assert!(run(&mut interpreter, "var (x, y) = (1, 2, 3);").is_err());
+        assert!(run(&mut interpreter, "(a, b) = 1;").is_err());
+        assert!(run(&mut interpreter, "(a, zz) = (10, 20);").is_err());
+        assert_eq!(get(&interpreter, "a"), Value::Number(3.0));
+    }
+
+    #[test]
+    fn test_enums() {
+        let mut interpreter = Interpreter::new();
+        run(&mut interpreter, "enum Shape { Circle(r), Rect(w, h), Empty }").unwrap();
+        run(&mut interpreter, "var a = Rect(1, 2) == Rect(1, 2); var b = Rect(1, 2) == Rect(2, 1); var c = Empty == Empty;").unwrap();
+        assert_eq!(get(&interpreter, "a"), Value::Bool(true));
+        assert_eq!(get(&interpreter, "b"), Value::Bool(false));
+        assert_eq!(get(&interpreter, "c"), Value::Bool(true));
+
+        run(&mut interpreter, "var Rect(w, h) = Rect(3, 4); var area = w * h;").unwrap();
+        assert_eq!(get(&interpreter, "area"), Value::Number(12.0));
+        run(&mut interpreter, "var shape = Rect(Circle(1), Empty);").unwrap();
+        assert_eq!(interpreter.stringify(&get(&interpreter, "shape")), "Rect(Circle(1), Empty)");
+
+        assert!(run(&mut interpreter, "Circle(1, 2);").is_err());
+        assert!(run(&mut interpreter, "var Circle(r) = Rect(1, 2);").is_err());
+        assert!(run(&mut interpreter, "Empty(1);").is_err());
+
+        // A same-named variant of another enum doesn't match.
+        run(&mut interpreter, "var old = Circle(5); enum Token { Circle(r) }").unwrap();
+        let error = run(&mut interpreter, "var Circle(r) = old;").unwrap_err();
+        assert_eq!(error.message, "Cannot destructure variant Shape.Circle with pattern Token.Circle.");
+        run(&mut interpreter, "var Circle(r) = Circle(6);").unwrap();
+        assert_eq!(get(&interpreter, "r"), Value::Number(6.0));
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let mut interpreter = Interpreter::new();
+        let span_of = |interpreter: &mut Interpreter, source: &str| match run(interpreter, source) {
+            Err(Error { kind, span: Some(span), .. }) => (kind, span.start, span.end),
+            other => panic!("Expected a located error, got {:?}", other),
+        };
+        assert_eq!(span_of(&mut interpreter, "print 1 + \"a\";"), (ErrorKind::Runtime, 8, 9));
+        assert_eq!(span_of(&mut interpreter, "print x;"), (ErrorKind::Resolution, 6, 7));
+        assert_eq!(span_of(&mut interpreter, "x = 1;"), (ErrorKind::Resolution, 0, 1));
+        assert_eq!(span_of(&mut interpreter, "for (c in 1) print c;"), (ErrorKind::Runtime, 10, 11));
+        assert_eq!(span_of(&mut interpreter, "var (a, b) = (1, 2, 3);"), (ErrorKind::Runtime, 5, 9));
+    }
+}
@@ -0,0 +1,45 @@
+//! A tree-walking interpreter for Lox that can be embedded in Rust programs.
+//!
+//! ```
+//! use interpreter::{Lox, Value};
+//!
+//! let mut lox = Lox::new();
+//! lox.register_fn("double", |n: f32| n * 2.0);
+//! lox.set_global("x", 20.0);
+//! assert_eq!(lox.eval("double(x) + 2;").unwrap(), Value::Number(42.0));
+//! ```
+
+pub mod codes;
+mod token;
+mod scanner;
+mod convert;
+mod error;
+mod expr;
+#[cfg(test)]
+mod ast_printer;
+mod parser;
+mod interpreter;
+mod stmt;
+mod environment;
+mod diagnostic;
+mod fix;
+mod lox;
+mod native;
+mod sink;
+mod suggest;
+mod range;
+mod typecheck;
+mod variant;
+
+pub use crate::codes::{explain, Explanation};
+pub use crate::convert::{ConversionError, FromValue, HostFunction, IntoHostResult, IntoValue};
+pub use crate::diagnostic::{Diagnostic, SourceFile, Suggestion};
+pub use crate::error::{Error, ErrorKind};
+pub use crate::fix::fix;
+pub use crate::interpreter::Value;
+pub use crate::lox::Lox;
+pub use crate::native::NativeFunction;
+pub use crate::range::Range;
+pub use crate::sink::{DiagnosticSink, OutputSink, StderrSink, StdoutSink, WriteSink};
+pub use crate::token::Span;
+pub use crate::variant::{Variant, VariantConstructor};
@@ -0,0 +1,339 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::codes;
+use crate::convert::{HostFunction, IntoValue};
+use crate::diagnostic::{Diagnostic, SourceFile};
+use crate::error::{Error, ErrorKind};
+use crate::interpreter::{Interpreter, Value};
+use crate::native::NativeFunction;
+use crate::parser::Parser;
+use crate::scanner;
+use crate::sink::{DiagnosticSink, OutputSink, StderrSink};
+use crate::stmt::Stmt;
+use crate::typecheck;
+
+/// An interpreter session: the globals defined so far and where its diagnostics go.
+/// Sessions share no state, so any number of them can run side by side, even on different threads.
+pub struct Lox {
+    interpreter: Interpreter,
+    sink: Box<dyn DiagnosticSink>,
+}
+
+impl Lox {
+    /// A session that prints to stdout and renders its diagnostics to stderr.
+    pub fn new() -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+            sink: Box::new(StderrSink),
+        }
+    }
+
+    /// Sends diagnostics to `sink` instead, e.g. a `WriteSink` or a closure.
+    pub fn with_sink(mut self, sink: impl DiagnosticSink + 'static) -> Self {
+        self.sink = Box::new(sink);
+        self
+    }
+
+    /// Sends the output of `print` statements to `output` instead, e.g. a `WriteSink` or a closure.
+    pub fn with_output(mut self, output: impl OutputSink + 'static) -> Self {
+        self.interpreter.set_output(output);
+        self
+    }
+
+    /// Takes back the sink given to `with_output`, e.g. to read what a `WriteSink<Vec<u8>>` captured or to flush
+    /// a buffered writer. Returns `None`, leaving the sink in place, if it isn't an `O`. Later output goes to stdout.
+    pub fn take_output<O: OutputSink>(&mut self) -> Option<O> {
+        self.interpreter.take_output()
+    }
+
+    /// Scans, parses, checks and runs `source`, which diagnostics call `name`.
+    /// Every diagnostic has been sent to the sink by the time this returns; the error is the first of them.
+    pub fn run(&mut self, name: &str, source: &str) -> Result<(), Error> {
+        self.execute(name, source).map(|_| ())
+    }
+
+    /// Scans, parses and checks `source` like `run`, but doesn't run it.
+    pub fn check(&mut self, name: &str, source: &str) -> Result<(), Error> {
+        let source = SourceFile {
+            name: name.to_string(),
+            text: source.to_string(),
+        };
+        self.compile(&source).map(|_| ())
+    }
+
+    pub fn run_file(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| {
+            Error::new(ErrorKind::Io, codes::CANNOT_READ, &format!("Cannot read '{}'.", path.display()), None).with_cause(e)
+        })?;
+        self.run(&path.display().to_string(), &source)
+    }
+
+    /// Runs `source` like `run` and returns the value of its final statement, e.g. `1 + 2` for `"var a = 1; a + 2;"`.
+    /// A `print` statement yields the value it printed and declarations yield nil.
+    pub fn eval(&mut self, source: &str) -> Result<Value, Error> {
+        self.execute("<eval>", source)
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.interpreter.global(name)
+    }
+
+    /// Defines (or redefines) a global variable visible to every later run.
+    pub fn set_global(&mut self, name: &str, value: impl IntoValue) {
+        self.interpreter.define_global(name, value.into_value());
+    }
+
+    /// Calls the global callable `name`, e.g. an enum constructor or a registered host function.
+    /// Errors are returned rather than sent to the sink, since there is no Lox source to point at.
+    pub fn call(&mut self, name: &str, arguments: Vec<Value>) -> Result<Value, Error> {
+        let callee = self.get_global(name)
+            .ok_or_else(|| self.interpreter.undefined_global(name))?;
+        self.interpreter.call(callee, arguments)
+    }
+
+    /// Defines a global function implemented in Rust. Returning `Err(message)` raises a runtime error at the call.
+    pub fn register<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        let function = move |arguments: &[Value]| function(arguments).map_err(|message| Error::unlocated(codes::HOST_FUNCTION_FAILED, &message));
+        self.set_global(name, Value::Native(NativeFunction::new(name, arity, Arc::new(function))));
+    }
+
+    /// Defines a global function from a typed closure such as `|a: f32, b: f32| a + b`.
+    /// Arguments are converted with `FromValue`; a wrong count or type raises a runtime error at the call.
+    pub fn register_fn<Args, F: HostFunction<Args>>(&mut self, name: &str, function: F) {
+        let arity = function.arity();
+        let registered = name.to_string();
+        let function = move |arguments: &[Value]| function.invoke(&registered, arguments);
+        self.set_global(name, Value::Native(NativeFunction::new(name, arity, Arc::new(function))));
+    }
+
+    fn execute(&mut self, name: &str, source: &str) -> Result<Value, Error> {
+        let source = SourceFile {
+            name: name.to_string(),
+            text: source.to_string(),
+        };
+        let statements = self.compile(&source)?;
+
+        let mut first_error = None;
+        let sink = &mut self.sink;
+        let value = self.interpreter.interpret(&statements, |error| {
+            sink.report(&error.to_diagnostic(), &source);
+            first_error.get_or_insert(error);
+        });
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(value),
+        }
+    }
+
+    /// Runs the stages before the interpreter, reporting the diagnostics of the first one that fails.
+    fn compile(&mut self, source: &SourceFile) -> Result<Vec<Stmt>, Error> {
+        let tokens = match scanner::scan_tokens(source.text.clone()) {
+            Ok(tokens) => tokens,
+            Err(diagnostics) => return Err(self.report_all(&diagnostics, source)),
+        };
+        let (statements, diagnostics) = Parser::new(tokens).parse();
+        // Running a partial program would only add noise to the syntax errors.
+        if !diagnostics.is_empty() {
+            return Err(self.report_all(&diagnostics, source));
+        }
+        if let Err(diagnostics) = typecheck::check(&statements) {
+            return Err(self.report_all(&diagnostics, source));
+        }
+        Ok(statements)
+    }
+
+    /// Reports a stage's diagnostics, returning the first as an error.
+    fn report_all(&mut self, diagnostics: &[Diagnostic], source: &SourceFile) -> Error {
+        for diagnostic in diagnostics {
+            self.sink.report(diagnostic, source);
+        }
+        Error::from_diagnostic(&diagnostics[0])
+    }
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use crate::sink::{StdoutSink, WriteSink};
+
+    use super::*;
+
+    /// A session whose diagnostics are collected as messages.
+    fn session() -> (Lox, Arc<Mutex<Vec<String>>>) {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&messages);
+        let lox = Lox::new().with_sink(move |diagnostic: &Diagnostic, _: &SourceFile| {
+            collected.lock().unwrap().push(diagnostic.message.clone());
+        });
+        (lox, messages)
+    }
+
+    #[test]
+    fn test_sessions_are_independent() {
+        let (mut first, first_messages) = session();
+        let (mut second, second_messages) = session();
+        first.run("first", "var a = 1;").unwrap();
+        assert!(first.run("first", "print a;").is_ok());
+        assert!(matches!(second.run("second", "print a;"), Err(Error { kind: ErrorKind::Resolution, .. })));
+        assert!(first_messages.lock().unwrap().is_empty());
+        assert_eq!(*second_messages.lock().unwrap(), vec!["Undefined variable 'a'.".to_string()]);
+    }
+
+    #[test]
+    fn test_stages() {
+        let (mut lox, messages) = session();
+        let kind = |result: Result<(), Error>| result.unwrap_err().kind;
+        assert_eq!(kind(lox.run("test", "var a = \"b;")), ErrorKind::Lexical);
+        assert_eq!(kind(lox.run("test", "var a = ; print (1;")), ErrorKind::Syntax);
+        assert_eq!(kind(lox.run("test", "var a: Num = 1;")), ErrorKind::Type);
+        assert_eq!(kind(lox.run("test", "print 1 - \"a\";")), ErrorKind::Type);
+        assert_eq!(kind(lox.run("test", "print -\"a\";")), ErrorKind::Type);
+        assert_eq!(kind(lox.run("test", "print 1 in \"a\";")), ErrorKind::Type);
+
+        let error = lox.run("test", "var a = 1;\nprint (1, 2) + a;").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Type);
+        assert_eq!(error.to_string(), "type error[E0302] at 2:14: Operands must be both numbers or strings.");
+        assert_eq!(messages.lock().unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_undefined_suggestions() {
+        let (mut lox, _) = session();
+        let help = |result: Result<Value, Error>| result.unwrap_err().help().map(str::to_string);
+        lox.eval("var count = 1;").unwrap();
+        assert_eq!(help(lox.eval("countt;")), Some("did you mean 'count'?".to_string()));
+        assert_eq!(help(lox.eval("{ var total = 2; coutn = totl; }")), Some("did you mean 'total'?".to_string()));
+        assert_eq!(help(lox.eval("{ var total = 2; coutn = total; }")), Some("did you mean 'count'?".to_string()));
+        assert_eq!(help(lox.eval("print tru;")), Some("did you mean 'true'?".to_string()));
+        assert_eq!(help(lox.eval("print xyz;")), None);
+        assert_eq!(help(lox.eval("print whle;")), None);
+        assert_eq!(help(lox.eval("tru = 1;")), None);
+        assert_eq!(help(lox.eval("(count, tru) = (1, 2);")), None);
+        lox.eval("var x = 1;").unwrap();
+        assert_eq!(help(lox.eval("print y;")), None);
+        lox.register_fn("greet", || "hi");
+        assert_eq!(help(lox.call("gret", vec![])), Some("did you mean 'greet'?".to_string()));
+    }
+
+    #[test]
+    fn test_explanation_examples() {
+        // These need a host function, a missing file or a failing writer rather than bad source.
+        let untestable = [codes::HOST_FUNCTION_FAILED, codes::CANNOT_READ, codes::CANNOT_WRITE];
+        for explanation in codes::EXPLANATIONS.iter().filter(|e| !untestable.contains(&e.code)) {
+            let (lox, _) = session();
+            let mut lox = lox.with_output(|_: &str| {});
+            let error = lox.run("example", explanation.erroneous).unwrap_err();
+            assert_eq!(error.code, explanation.code, "{}", explanation.erroneous);
+            let (lox, messages) = session();
+            let result = lox.with_output(|_: &str| {}).run("example", explanation.corrected);
+            assert!(result.is_ok(), "{}: {:?}", explanation.code, messages.lock().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_embedding() {
+        let (mut lox, messages) = session();
+        assert_eq!(lox.eval("var a = 1; a + 2;").unwrap(), Value::Number(3.0));
+        assert_eq!(lox.eval("var b = 1;").unwrap(), Value::Nil);
+        assert_eq!(lox.get_global("a"), Some(Value::Number(1.0)));
+        assert_eq!(lox.get_global("c"), None);
+
+        lox.set_global("name", Value::String("lox".to_string()));
+        assert_eq!(lox.eval("name + \"!\";").unwrap(), Value::String("lox!".to_string()));
+
+        lox.register("twice", 1, |arguments| match &arguments[0] {
+            Value::Number(n) => Ok(Value::Number(n * 2.0)),
+            other => Err(format!("Expected a number but got {}.", other.type_name())),
+        });
+        assert_eq!(lox.eval("twice(a + 2);").unwrap(), Value::Number(6.0));
+        assert!(lox.eval("twice(\"a\");").is_err());
+        assert!(lox.eval("twice(1, 2);").is_err());
+        assert_eq!(messages.lock().unwrap().as_slice(), ["Expected a number but got string.", "Expected 1 arguments but got 2."]);
+
+        lox.eval("enum Shape { Circle(r) }").unwrap();
+        assert_eq!(lox.call("twice", vec![Value::Number(4.0)]).unwrap(), Value::Number(8.0));
+        let circle = lox.call("Circle", vec![Value::Number(1.0)]).unwrap();
+        assert!(matches!(circle, Value::Variant(variant) if variant.name == "Circle"));
+        assert!(lox.call("a", vec![]).is_err());
+        assert!(lox.call("missing", vec![]).is_err());
+
+        let error = lox.run_file("does/not/exist.lox").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Io);
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    fn test_typed_host_functions() {
+        let (mut lox, messages) = session();
+        lox.register_fn("hypot", |a: f32, b: f32| (a * a + b * b).sqrt());
+        lox.register_fn("repeat", |s: String, n: usize| s.repeat(n));
+        lox.register_fn("swap", |pair: (Value, Value)| (pair.1, pair.0));
+        lox.register_fn("first", |values: Vec<Value>| values.into_iter().next());
+        lox.set_global("origin", (0.0, 0.0));
+
+        assert_eq!(lox.eval("hypot(3, 4);").unwrap(), Value::Number(5.0));
+        assert_eq!(lox.eval("repeat(\"ab\", 2);").unwrap(), Value::String("abab".to_string()));
+        assert_eq!(lox.eval("swap((1, \"a\"));").unwrap(), ("a", 1.0).into_value());
+        assert_eq!(lox.eval("first((7,));").unwrap(), Value::Number(7.0));
+        assert_eq!(lox.eval("origin;").unwrap(), (0.0, 0.0).into_value());
+
+        assert!(lox.eval("repeat(\"ab\", 1.5);").is_err());
+        assert!(lox.eval("hypot(1, true);").is_err());
+        assert!(lox.eval("hypot(1);").is_err());
+        assert_eq!(messages.lock().unwrap().as_slice(), [
+            "Invalid argument 2 to 'repeat': expected usize but got number 1.5.",
+            "Invalid argument 2 to 'hypot': expected number but got bool.",
+            "Expected 2 arguments but got 1.",
+        ]);
+    }
+
+    #[test]
+    fn test_output() {
+        let (lox, messages) = session();
+        let printed = Arc::new(Mutex::new(Vec::new()));
+        let lines = Arc::clone(&printed);
+        let mut lox = lox.with_output(move |line: &str| lines.lock().unwrap().push(line.to_string()));
+        lox.run("test", "print 1; print x; print (1, \"a\");").unwrap_err();
+        assert_eq!(*printed.lock().unwrap(), vec!["1".to_string(), "(1, a)".to_string()]);
+        assert_eq!(messages.lock().unwrap().len(), 1);
+
+        let mut lox = Lox::new().with_output(WriteSink(io::sink()));
+        assert_eq!(lox.eval("print 2;").unwrap(), Value::Number(2.0));
+
+        let mut lox = Lox::new().with_output(WriteSink(Vec::new()));
+        lox.run("test", "print 1; print \"two\";").unwrap();
+        assert!(lox.take_output::<StdoutSink>().is_none());
+        let written = lox.take_output::<WriteSink<Vec<u8>>>().unwrap().into_inner();
+        assert_eq!(String::from_utf8(written).unwrap(), "1\ntwo\n");
+        assert!(lox.take_output::<WriteSink<Vec<u8>>>().is_none());
+    }
+
+    #[test]
+    fn test_parallel_sessions() {
+        let handles: Vec<_> = (0..4).map(|i| thread::spawn(move || {
+            let (mut lox, messages) = session();
+            lox.run("thread", &format!("var a = {}; var b = a * 2;", i)).unwrap();
+            let result = lox.run("thread", "print c;");
+            let reported = messages.lock().unwrap().len();
+            (result.is_err(), reported)
+        })).collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), (true, 1));
+        }
+    }
+}
@@ -1,26 +1,9 @@
 use std::env;
+use std::error::Error;
 use std::fs;
 use std::io::{self, Write};
-use std::path::Path;
-use std::sync::Mutex;
 
-use once_cell::sync::Lazy;
-
-use crate::interpreter::Interpreter;
-use crate::parser::Parser;
-
-mod token;
-mod scanner;
-mod error;
-mod expr;
-mod ast_printer;
-mod parser;
-mod interpreter;
-mod stmt;
-mod environment;
-
-static HAD_ERROR: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
-static INTERPRETER: Lazy<Mutex<Interpreter>> = Lazy::new(|| Mutex::new(Interpreter::new()));
+use interpreter::{explain, fix, ErrorKind, Lox};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -28,22 +11,60 @@ fn main() {
     match args.len() {
         1 => run_prompt(),
         2 => run_file(&args[1]),
+        3 if args[1] == "explain" => run_explain(&args[2]),
+        3 if args[1] == "--fix" => run_fix(&args[2]),
         _ => {
-            eprintln!("Usage: rlox [script]");
+            eprintln!("Usage: rlox [script] | rlox --fix <script> | rlox explain <code>");
             std::process::exit(64);
         }
     }
 }
 
 fn run_file(path: &str) {
-    match fs::read_to_string(Path::new(path)) {
-        Ok(contents) => {
-            run(&contents);
-            if *HAD_ERROR.lock().unwrap() {
-                std::process::exit(65);
+    // Errors in the script itself have already been reported by the session.
+    match Lox::new().run_file(path) {
+        Ok(()) => {}
+        Err(err) => match err.kind {
+            ErrorKind::Lexical | ErrorKind::Syntax | ErrorKind::Type => std::process::exit(-1),
+            ErrorKind::Io if err.span.is_none() => {
+                eprintln!("Error reading file {}: {}", path, err.source().map_or(err.to_string(), |cause| cause.to_string()));
             }
+            _ => std::process::exit(65),
+        },
+    }
+}
+
+/// Applies the suggested fixes to the script in place, then reports whatever is still wrong with it.
+fn run_fix(path: &str) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("Error reading file {}: {}", path, err);
+            std::process::exit(74);
+        }
+    };
+    // `fix` only reports fixes that leave the script parsing cleanly; otherwise the file is left as it was and
+    // the check below reports what is still wrong.
+    let (fixed, applied) = fix(&source);
+    if applied > 0 {
+        if let Err(err) = fs::write(path, &fixed) {
+            eprintln!("Error writing file {}: {}", path, err);
+            std::process::exit(74);
+        }
+    }
+    println!("Applied {} fix{} to {}.", applied, if applied == 1 { "" } else { "es" }, path);
+    if Lox::new().check(path, &fixed).is_err() {
+        std::process::exit(-1);
+    }
+}
+
+fn run_explain(code: &str) {
+    match explain(code) {
+        Some(explanation) => print!("{}", explanation.render()),
+        None => {
+            eprintln!("No explanation for error code '{}'.", code);
+            std::process::exit(64);
         }
-        Err(err) => eprintln!("Error reading file {}: {}", path, err),
     }
 }
 
@@ -51,6 +72,7 @@ fn run_prompt() {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     let mut input = String::new();
+    let mut lox = Lox::new();
 
     println!("Running prompt. Type your commands below:");
 
@@ -65,8 +87,8 @@ fn run_prompt() {
                 if trim.is_empty() {
                     continue;
                 }
-                run(trim);
-                *HAD_ERROR.lock().unwrap() = false;
+                // Errors have been reported; the prompt carries on with the next line.
+                let _ = lox.run("<prompt>", trim);
             }
             Err(err) => {
                 eprintln!("Error reading input: {}", err);
@@ -75,20 +97,3 @@ fn run_prompt() {
         }
     }
 }
-
-fn run(source: &str) {
-    match scanner::scan_tokens(source.to_string()) {
-        Ok(tokens) => {
-            let mut parser = Parser::new(tokens);
-            match parser.parse() {
-                Ok(stmt) => {
-                    INTERPRETER.lock().unwrap().interpret(stmt);
-                }
-                Err(_) => { std::process::exit(-1); }
-            };
-        }
-        Err(_) => {
-            std::process::exit(-1);
-        }
-    };
-}
@@ -0,0 +1,43 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::error::Error;
+use crate::interpreter::Value;
+
+/// The body of a host function: the call's arguments in, a value or an error out.
+pub type NativeFn = dyn Fn(&[Value]) -> Result<Value, Error> + Send + Sync;
+
+/// A function implemented in Rust and registered with `Lox::register`.
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: usize,
+    function: Arc<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new(name: &str, arity: usize, function: Arc<NativeFn>) -> Self {
+        Self {
+            name: name.to_string(),
+            arity,
+            function,
+        }
+    }
+
+    pub fn call(&self, arguments: &[Value]) -> Result<Value, Error> {
+        (self.function)(arguments)
+    }
+}
+
+/// Two natives are equal only if they are the same registered function.
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Arc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
@@ -1,37 +1,84 @@
-use crate::error::{Error, error_tok};
-use crate::expr::Expr;
+use std::mem;
+
+use crate::diagnostic::Diagnostic;
+use crate::codes;
+use crate::error::{Error, ErrorKind};
+use crate::expr::{Expr, Pattern};
+use crate::scanner;
 use crate::stmt::Stmt;
-use crate::token::{Token, TokenType};
+use crate::suggest;
+use crate::token::{Span, Token, TokenType};
+use crate::typecheck::Type;
+
+/// Keywords that begin a statement; after a syntax error, parsing resumes at the next one.
+const STATEMENT_KEYWORDS: [TokenType; 9] = [
+    TokenType::Class,
+    TokenType::Fun,
+    TokenType::Var,
+    TokenType::Enum,
+    TokenType::For,
+    TokenType::If,
+    TokenType::While,
+    TokenType::Print,
+    TokenType::Return,
+];
+
+/// Keywords `declaration` and `statement` parse, so the only ones worth suggesting in place of a misspelling.
+const PARSED_KEYWORDS: [TokenType; 4] = [TokenType::Var, TokenType::Enum, TokenType::For, TokenType::Print];
 
 /*
 expression     → assignment ;
-assignment     → IDENTIFIER "=" assignment
+assignment     → ( IDENTIFIER | tuple ) "=" assignment
                | equality ;
 equality       → comparison ( ( "!=" | "==" ) comparison )* ; (...)* means 0 or more
-comparison     → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
+comparison     → range ( ( ">" | ">=" | "<" | "<=" | "in" ) range )* ;
+range          → term ( ( ".." | "..=" ) term ( "step" term )? )? ;
 term           → factor ( ( "-" | "+" ) factor )* ;
 factor         → unary ( ( "/" | "*" ) unary )* ;
-unary          → ( "!" | "-" ) unary | primary ;
-primary        → NUMBER | STRING | "true" | "false" | "nil" | "(" expression ") | IDENTIFIER" ;
+unary          → ( "!" | "-" ) unary | call ;
+call           → primary ( "(" arguments? ")" )* ;
+arguments      → expression ( "," expression )* ;
+primary        → NUMBER | STRING | "true" | "false" | "nil" | "(" expression ")" | tuple | IDENTIFIER ;
  */
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Parser {
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, Error> {
+    /// Parses every declaration it can, skipping to the next statement after a syntax error.
+    /// Returns the statements that parsed along with every error raised on the way.
+    pub fn parse(&mut self) -> (Vec<Stmt>, Vec<Diagnostic>) {
         let mut statements = Vec::new();
         while !self.is_at_end() {
-            statements.push(self.declaration()?)
+            if let Some(stmt) = self.recovering_declaration() {
+                statements.push(stmt);
+            }
         }
-        Ok(statements)
+        (statements, mem::take(&mut self.diagnostics))
     }
 
     pub fn new(tokens: Vec<Token>) -> Self {
         Self {
             tokens,
             current: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn recovering_declaration(&mut self) -> Option<Stmt> {
+        let start = self.current;
+        match self.declaration() {
+            Ok(stmt) => Some(stmt),
+            Err(_) => {
+                self.suggest_keyword(start);
+                // A missing ';' is reported at the next statement's keyword, which must not be skipped.
+                if self.current == start || !self.at_statement_start() {
+                    self.synchonize();
+                }
+                None
+            }
         }
     }
 
@@ -50,8 +97,10 @@ impl Parser {
                         if let Expr::Variable(name) = expr {
                             return Ok(Expr::Assign(name, Box::new(value)));
                         }
-                        error_tok(&equals, "Invalid assignment target.");
-                        return Err(Error::ParseError(Option::from("Invalid assignment target.".to_string())));
+                        if let Some(pattern) = Parser::assignment_pattern(expr) {
+                            return Ok(Expr::AssignPattern(pattern, Box::new(value)));
+                        }
+                        return Err(self.error(&equals, codes::INVALID_ASSIGNMENT_TARGET, "Invalid assignment target."));
                     }
                     Err(e) => return Err(e),
                 },
@@ -61,14 +110,52 @@ impl Parser {
         expr
     }
 
+    /// Reinterprets an already parsed expression such as `(a, (b, c))` as an assignment target.
+    fn assignment_pattern(expr: Expr) -> Option<Pattern> {
+        match expr {
+            Expr::Variable(name) => Some(Pattern::Name(name)),
+            Expr::Grouping(expr, _) => Parser::assignment_pattern(*expr),
+            Expr::Tuple(elements, _) => elements.into_iter()
+                .map(Parser::assignment_pattern)
+                .collect::<Option<Vec<_>>>()
+                .map(Pattern::Tuple),
+            Expr::Call(callee, _, arguments) => match *callee {
+                Expr::Variable(name) => arguments.into_iter()
+                    .map(Parser::assignment_pattern)
+                    .collect::<Option<Vec<_>>>()
+                    .map(|fields| Pattern::Variant(name, fields)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     fn equality(&mut self) -> Result<Expr, Error> {
         let token_types = &[TokenType::BangEqual, TokenType::EqualEqual];
         self.parse_binary_expr(token_types, Parser::comparison)
     }
 
     fn comparison(&mut self) -> Result<Expr, Error> {
-        let token_types = &[TokenType::Greater, TokenType::GreaterEqual, TokenType::Less, TokenType::LessEqual];
-        self.parse_binary_expr(token_types, Parser::term)
+        let token_types = &[TokenType::Greater, TokenType::GreaterEqual, TokenType::Less, TokenType::LessEqual, TokenType::In];
+        self.parse_binary_expr(token_types, Parser::range)
+    }
+
+    fn range(&mut self) -> Result<Expr, Error> {
+        let start = self.term()?;
+        if !self.match_token(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            return Ok(start);
+        }
+        let op = self.previous().clone();
+        let end = self.term()?;
+        // `step` is contextual so it stays usable as a variable name.
+        let step = match self.check(TokenType::Identifier) && self.current_token().lexeme == "step" {
+            true => {
+                self.advance();
+                Some(Box::new(self.term()?))
+            }
+            false => None
+        };
+        Ok(Expr::Range(Box::new(start), op, Box::new(end), step))
     }
 
     fn term(&mut self) -> Result<Expr, Error> {
@@ -105,34 +192,67 @@ impl Parser {
                 Err(e) => return Err(e),
             };
         }
-        self.primary()
+        self.call()
+    }
+
+    fn call(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.primary()?;
+        while self.match_token(&[TokenType::LeftParen]) {
+            let mut arguments = Vec::new();
+            if !self.check(TokenType::RightParen) {
+                arguments.push(self.expression()?);
+                while self.match_token(&[TokenType::Comma]) {
+                    arguments.push(self.expression()?);
+                }
+            }
+            let paren = self.consume_terminator(TokenType::RightParen, "Expect ')' after arguments.")?.clone();
+            expr = Expr::Call(Box::new(expr), paren, arguments);
+        }
+        Ok(expr)
     }
 
     fn primary(&mut self) -> Result<Expr, Error> {
         if self.match_token(&[TokenType::Number]) {
-            return Ok(Expr::LiteralNum(self.previous().clone().literal_num));
+            return Ok(Expr::LiteralNum(self.previous().literal_num, self.previous().span));
         }
         if self.match_token(&[TokenType::String]) {
-            return Ok(Expr::LiteralStr(self.previous().clone().literal_str.clone()));
+            return Ok(Expr::LiteralStr(self.previous().literal_str.clone(), self.previous().span));
         }
         if self.match_token(&[TokenType::True]) {
-            return Ok(Expr::LiteralBool(Some(true)));
+            return Ok(Expr::LiteralBool(Some(true), self.previous().span));
         }
         if self.match_token(&[TokenType::False]) {
-            return Ok(Expr::LiteralBool(Some(false)));
+            return Ok(Expr::LiteralBool(Some(false), self.previous().span));
         }
         if self.match_token(&[TokenType::Nil]) {
-            return Ok(Expr::LiteralStr(None));
+            return Ok(Expr::LiteralStr(None, self.previous().span));
         }
         if self.match_token(&[TokenType::Identifier]) {
             return Ok(Expr::Variable(self.previous().clone()));
         }
         if self.match_token(&[TokenType::LeftParen]) {
+            let paren = self.previous().span;
             let expr = self.expression()?;
-            self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
-            return Ok(Expr::Grouping(Box::new(expr)));
+            if self.match_token(&[TokenType::Comma]) {
+                return self.tuple(paren, expr);
+            }
+            let closing = self.consume_terminator(TokenType::RightParen, "Expect ')' after expression.")?.span;
+            return Ok(Expr::Grouping(Box::new(expr), paren.to(closing)));
+        }
+        let token = self.current_token().clone();
+        Err(self.error(&token, codes::EXPECTED_EXPRESSION, "Expect expression."))
+    }
+
+    fn tuple(&mut self, paren: Span, first: Expr) -> Result<Expr, Error> {
+        let mut elements = vec![first];
+        while !self.check(TokenType::RightParen) && !self.is_at_end() {
+            elements.push(self.expression()?);
+            if !self.match_token(&[TokenType::Comma]) {
+                break;
+            }
         }
-        Err(Error::ParseError(Option::from("Expect expression.".to_string())))
+        let closing = self.consume_terminator(TokenType::RightParen, "Expect ')' after tuple elements.")?.span;
+        Ok(Expr::Tuple(elements, paren.to(closing)))
     }
 
     fn match_token(&mut self, types: &[TokenType]) -> bool {
@@ -149,6 +269,10 @@ impl Parser {
         !self.is_at_end() && self.current_token().token_type == token_type
     }
 
+    fn check_next(&self, token_type: TokenType) -> bool {
+        self.tokens.get(self.current + 1).is_some_and(|token| token.token_type == token_type)
+    }
+
     fn current_token(&self) -> &Token {
         &self.tokens[self.current]
     }
@@ -163,15 +287,60 @@ impl Parser {
     }
 
     fn is_at_end(&self) -> bool {
-        self.current >= self.tokens.len()
+        self.current_token().token_type == TokenType::Eof
     }
 
     fn consume(&mut self, token_type: TokenType, msg: &str) -> Result<&Token, Error> {
         if self.check(token_type) {
             return Ok(self.advance());
         }
-        error_tok(self.current_token(), msg);
-        Err(Error::ParseError(Option::from(msg.to_string())))
+        Err(self.expected(token_type, msg))
+    }
+
+    /// Consumes the ';' or ')' that closes a statement or expression. A missing one belongs right after the last
+    /// token that parsed, so the error suggests adding it there, but only if the statement clearly ends there:
+    /// `print 1 2;` is missing an operator, not a ';'.
+    fn consume_terminator(&mut self, token_type: TokenType, msg: &str) -> Result<&Token, Error> {
+        if self.check(token_type) {
+            return Ok(self.advance());
+        }
+        let fixable = self.current > 0 && self.ends_statement(self.current_token());
+        let error = self.expected(token_type, msg);
+        let text = match token_type {
+            TokenType::Semicolon => ";",
+            TokenType::RightParen => ")",
+            _ => return Err(error),
+        };
+        if fixable {
+            let span = self.previous().span.after();
+            if let Some(diagnostic) = self.diagnostics.pop() {
+                self.diagnostics.push(diagnostic.with_suggestion(span, text, &format!("add '{}' here", text)));
+            }
+        }
+        Err(error)
+    }
+
+    /// Records that `token_type` was expected at the current token.
+    fn expected(&mut self, token_type: TokenType, msg: &str) -> Error {
+        let token = self.current_token().clone();
+        let code = match token_type {
+            TokenType::Semicolon => codes::MISSING_SEMICOLON,
+            TokenType::RightParen | TokenType::RightBrace => codes::UNCLOSED_DELIMITER,
+            TokenType::Identifier => codes::EXPECTED_IDENTIFIER,
+            _ => codes::UNEXPECTED_TOKEN,
+        };
+        self.error(&token, code, msg)
+    }
+
+    /// Records a syntax error at `token` and returns it for the caller to propagate.
+    fn error(&mut self, token: &Token, code: &'static str, message: &str) -> Error {
+        let error = Error::syntax(code, message, token.span);
+        let diagnostic = error.to_diagnostic();
+        self.diagnostics.push(match token.token_type {
+            TokenType::Eof => diagnostic.with_label("at end"),
+            _ => diagnostic,
+        });
+        error
     }
     fn synchonize(&mut self) {
         self.advance();
@@ -179,15 +348,7 @@ impl Parser {
             if self.previous().token_type == TokenType::Semicolon {
                 return;
             }
-            let token_type = self.current_token().token_type;
-            if token_type == TokenType::Class
-                || token_type == TokenType::Fun
-                || token_type == TokenType::Var
-                || token_type == TokenType::For
-                || token_type == TokenType::If
-                || token_type == TokenType::While
-                || token_type == TokenType::Print
-                || token_type == TokenType::Return {
+            if self.at_statement_start() {
                 return;
             } else {
                 self.advance();
@@ -195,7 +356,46 @@ impl Parser {
         }
     }
 
+    /// The span from `start` through the last token consumed.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous().span)
+    }
+
+    /// Whether `token` can't continue the statement before it: it starts a new line or statement, or closes one.
+    fn ends_statement(&self, token: &Token) -> bool {
+        token.span.start_line > self.previous().span.end_line
+            || STATEMENT_KEYWORDS.contains(&token.token_type)
+            || matches!(token.token_type, TokenType::Semicolon | TokenType::RightBrace | TokenType::Eof)
+    }
+
+    fn at_statement_start(&self) -> bool {
+        STATEMENT_KEYWORDS.contains(&self.current_token().token_type)
+    }
+
+    /// An identifier directly followed by a syntax error at the start of a statement, as in `pritn x;`,
+    /// is most likely a misspelled statement keyword. Adds the closest one as help to the error.
+    fn suggest_keyword(&mut self, start: usize) {
+        let first = &self.tokens[start];
+        if first.token_type != TokenType::Identifier || self.current != start + 1 {
+            return;
+        }
+        let keywords = scanner::keywords()
+            .filter(|(_, token_type)| PARSED_KEYWORDS.contains(token_type))
+            .map(|(keyword, _)| keyword);
+        if let Some(keyword) = suggest::closest(&first.lexeme, keywords) {
+            let help = format!("did you mean '{}'?", keyword);
+            let label = format!("'{}' is not a keyword", first.lexeme);
+            let span = first.span;
+            if let Some(diagnostic) = self.diagnostics.pop() {
+                self.diagnostics.push(diagnostic.with_secondary(span, &label).with_help(&help));
+            }
+        }
+    }
+
     fn statement(&mut self) -> Result<Stmt, Error> {
+        if self.match_token(&[TokenType::For]) {
+            return self.for_statement();
+        }
         if self.match_token(&[TokenType::Print]) {
             return self.print_statement();
         }
@@ -205,40 +405,182 @@ impl Parser {
         self.expression_statement()
     }
 
+    fn for_statement(&mut self) -> Result<Stmt, Error> {
+        let keyword = self.previous().span;
+        self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+        let mut patterns = vec![self.pattern()?];
+        while self.match_token(&[TokenType::Comma]) {
+            patterns.push(self.pattern()?);
+        }
+        let pattern = match patterns.len() {
+            1 => patterns.remove(0),
+            _ => Pattern::Tuple(patterns)
+        };
+        self.consume(TokenType::In, "Expect 'in' after loop variables.")?;
+        let iterable = self.expression()?;
+        self.consume_terminator(TokenType::RightParen, "Expect ')' after for clauses.")?;
+        let body = self.statement()?;
+        Ok(Stmt::ForIn(pattern, iterable, Box::new(body), self.span_from(keyword)))
+    }
+
     fn print_statement(&mut self) -> Result<Stmt, Error> {
+        let keyword = self.previous().span;
         let value = self.expression()?;
-        self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
-        Ok(Stmt::Print(value))
+        self.consume_terminator(TokenType::Semicolon, "Expect ';' after value.")?;
+        Ok(Stmt::Print(value, self.span_from(keyword)))
     }
 
     fn declaration(&mut self) -> Result<Stmt, Error> {
+        if self.match_token(&[TokenType::Enum]) {
+            return self.enum_declaration();
+        }
         if self.match_token(&[TokenType::Var]) {
             return self.var_declaration();
         }
         self.statement()
     }
 
+    fn enum_declaration(&mut self) -> Result<Stmt, Error> {
+        let keyword = self.previous().span;
+        let name = self.consume(TokenType::Identifier, "Expect enum name.")?.clone();
+        self.consume(TokenType::LeftBrace, "Expect '{' before enum body.")?;
+        let mut variants = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let variant = self.consume(TokenType::Identifier, "Expect variant name.")?.clone();
+            let fields = match self.match_token(&[TokenType::LeftParen]) {
+                true => {
+                    let mut fields = Vec::new();
+                    if !self.check(TokenType::RightParen) {
+                        fields.push(self.consume(TokenType::Identifier, "Expect field name.")?.clone());
+                        while self.match_token(&[TokenType::Comma]) {
+                            fields.push(self.consume(TokenType::Identifier, "Expect field name.")?.clone());
+                        }
+                    }
+                    self.consume_terminator(TokenType::RightParen, "Expect ')' after variant fields.")?;
+                    Some(fields)
+                }
+                false => None
+            };
+            variants.push((variant, fields));
+            if !self.match_token(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after enum body.")?;
+        Ok(Stmt::Enum(name, variants, self.span_from(keyword)))
+    }
+
     fn var_declaration(&mut self) -> Result<Stmt, Error> {
+        let keyword = self.previous().span;
+        if self.check(TokenType::LeftParen) || (self.check(TokenType::Identifier) && self.check_next(TokenType::LeftParen)) {
+            let pattern = self.pattern()?;
+            self.consume(TokenType::Equal, "Expect '=' after destructuring pattern.")?;
+            let value = self.expression()?;
+            self.consume_terminator(TokenType::Semicolon, "Expect ';' after variable declaration.")?;
+            return Ok(Stmt::VarPattern(pattern, value, self.span_from(keyword)));
+        }
         let name = self.consume(TokenType::Identifier, "Expect variable name.")?.clone();
+        let annotation = match self.match_token(&[TokenType::Colon]) {
+            true => Some(self.type_annotation()?),
+            false => None
+        };
         let value = match self.match_token(&[TokenType::Equal]) {
             true => Some(self.expression()?),
             false => None
         };
-        self.consume(TokenType::Semicolon, "Expect ';' after variable declaration.")?;
-        Ok(Stmt::Var(name, value))
+        self.consume_terminator(TokenType::Semicolon, "Expect ';' after variable declaration.")?;
+        Ok(Stmt::Var(name, annotation, value, self.span_from(keyword)))
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, Error> {
+        if self.match_token(&[TokenType::LeftParen]) {
+            // As in expressions, `(a)` only groups and a trailing comma makes `(a,)` a 1-tuple.
+            let first = self.pattern()?;
+            if !self.match_token(&[TokenType::Comma]) {
+                self.consume_terminator(TokenType::RightParen, "Expect ')' after pattern.")?;
+                return Ok(first);
+            }
+            let mut elements = vec![first];
+            while !self.check(TokenType::RightParen) && !self.is_at_end() {
+                elements.push(self.pattern()?);
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume_terminator(TokenType::RightParen, "Expect ')' after pattern.")?;
+            return Ok(Pattern::Tuple(elements));
+        }
+        let name = self.consume(TokenType::Identifier, "Expect variable name.")?.clone();
+        if self.match_token(&[TokenType::LeftParen]) {
+            let mut fields = Vec::new();
+            if !self.check(TokenType::RightParen) {
+                fields.push(self.pattern()?);
+                while self.match_token(&[TokenType::Comma]) {
+                    fields.push(self.pattern()?);
+                }
+            }
+            self.consume_terminator(TokenType::RightParen, "Expect ')' after variant pattern.")?;
+            return Ok(Pattern::Variant(name, fields));
+        }
+        Ok(Pattern::Name(name))
+    }
+
+    fn type_annotation(&mut self) -> Result<Type, Error> {
+        let declared = match self.match_token(&[TokenType::LeftParen]) {
+            true => {
+                // Grouped and tuple types follow the same rule as patterns.
+                let first = self.type_annotation()?;
+                match self.match_token(&[TokenType::Comma]) {
+                    true => {
+                        let mut elements = vec![first];
+                        while !self.check(TokenType::RightParen) && !self.is_at_end() {
+                            elements.push(self.type_annotation()?);
+                            if !self.match_token(&[TokenType::Comma]) {
+                                break;
+                            }
+                        }
+                        self.consume_terminator(TokenType::RightParen, "Expect ')' after tuple type.")?;
+                        Type::Tuple(elements)
+                    }
+                    false => {
+                        self.consume_terminator(TokenType::RightParen, "Expect ')' after type.")?;
+                        first
+                    }
+                }
+            }
+            false => {
+                let name = self.consume(TokenType::Identifier, "Expect type name.")?.clone();
+                match Type::from_name(&name.lexeme) {
+                    Some(declared) => declared,
+                    None => {
+                        let error = Error::new(ErrorKind::Type, codes::UNKNOWN_TYPE, "Unknown type.", Some(name.span));
+                        self.diagnostics.push(error.to_diagnostic());
+                        return Err(error);
+                    }
+                }
+            }
+        };
+        match self.match_token(&[TokenType::Question]) {
+            true => Ok(declared.optional()),
+            false => Ok(declared)
+        }
     }
     fn expression_statement(&mut self) -> Result<Stmt, Error> {
+        let start = self.current_token().span;
         let value = self.expression()?;
-        self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
-        Ok(Stmt::Expression(value))
+        self.consume_terminator(TokenType::Semicolon, "Expect ';' after value.")?;
+        Ok(Stmt::Expression(value, self.span_from(start)))
     }
     fn block_statement(&mut self) -> Result<Stmt, Error> {
+        let brace = self.previous().span;
         let mut stmts = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            stmts.push(self.declaration()?);
+            if let Some(stmt) = self.recovering_declaration() {
+                stmts.push(stmt);
+            }
         }
         self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
-        Ok(Stmt::Block(stmts))
+        Ok(Stmt::Block(stmts, self.span_from(brace)))
     }
 }
 
@@ -252,20 +594,81 @@ mod tests {
             Token::new(TokenType::Number, "123", 1, None, Some(123.0)),
             Token::new(TokenType::Star, "*", 1, None, None),
             Token::new(TokenType::Number, "45.67", 1, None, Some(45.67)),
+            Token::new(TokenType::Semicolon, ";", 1, None, None),
             Token::new(TokenType::Eof, "", 1, None, None),
         ];
         let mut parser = Parser::new(tokens);
-        match parser.parse() {
-            Ok(stmts) => {
-                for stmt in stmts {
-                    let ast_printer = crate::ast_printer::print_stmt(&stmt);
-                    assert_eq!(ast_printer, "(* 123 45.67)");
-                }
-            }
-            Err(e) => {
-                assert!(false);
-            }
+        let (stmts, errors) = parser.parse();
+        assert!(errors.is_empty(), "Errors: {:?}", errors);
+        for stmt in stmts {
+            let ast_printer = crate::ast_printer::print_stmt(&stmt);
+            assert_eq!(ast_printer, "(* 123 45.67)");
         }
     }
-}
 
+    #[test]
+    fn test_spans() {
+        let tokens = crate::scanner::scan_tokens("print (1 + 2) *\n  x;".to_string()).unwrap();
+        let (stmts, errors) = Parser::new(tokens).parse();
+        assert!(errors.is_empty(), "Errors: {:?}", errors);
+        let span = stmts[0].span();
+        // From the `print` keyword through the `;`.
+        assert_eq!((span.start, span.end), (0, 20));
+        assert_eq!((span.start_line, span.start_column), (1, 1));
+        assert_eq!((span.end_line, span.end_column), (2, 5));
+        if let Stmt::Print(Expr::Binary(left, _, _), _) = &stmts[0] {
+            assert_eq!((left.span().start, left.span().end), (6, 13));
+        } else {
+            panic!("Expected a binary print statement");
+        }
+
+        let tokens = crate::scanner::scan_tokens("var a = 1;\n{\n}\nfor (c in \"ab\") {}".to_string()).unwrap();
+        let (stmts, _) = Parser::new(tokens).parse();
+        let spans: Vec<_> = stmts.iter().map(|stmt| (stmt.span().start, stmt.span().end, stmt.span().start_line)).collect();
+        assert_eq!(spans, [(0, 10, 1), (11, 14, 2), (15, 33, 4)]);
+    }
+
+    #[test]
+    fn test_recovery() {
+        let source = "var a = ;\nprint a;\n{ print (1; var b = 2; }\nvar c = 3\nprint c;\n";
+        let tokens = crate::scanner::scan_tokens(source.to_string()).unwrap();
+        let (stmts, errors) = Parser::new(tokens).parse();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(stmts.len(), 3);
+        assert!(matches!(&stmts[0], Stmt::Print(..)));
+        assert!(matches!(&stmts[1], Stmt::Block(block, _) if block.len() == 1));
+        assert!(matches!(&stmts[2], Stmt::Print(..)));
+    }
+
+    #[test]
+    fn test_fix_suggestions() {
+        let suggestions = |source: &str| {
+            let tokens = crate::scanner::scan_tokens(source.to_string()).unwrap();
+            let (_, errors) = Parser::new(tokens).parse();
+            errors.iter()
+                .map(|error| error.suggestions.iter().map(|s| (s.span.start, s.replacement.clone())).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(suggestions("var a = 1\nprint (a;"), [vec![(9, ";".to_string())], vec![(18, ")".to_string())]]);
+        assert_eq!(suggestions("print f(1, (2, 3)\n"), [vec![(17, ")".to_string())]]);
+        // The next token doesn't end the statement, so the mistake is likely elsewhere.
+        assert_eq!(suggestions("print 1 2;"), [vec![]]);
+        assert_eq!(suggestions("print (1 + 2 3);"), [vec![]]);
+    }
+
+    #[test]
+    fn test_keyword_suggestions() {
+        let help = |source: &str| {
+            let tokens = crate::scanner::scan_tokens(source.to_string()).unwrap();
+            let (_, errors) = Parser::new(tokens).parse();
+            errors.into_iter().map(|error| error.help).collect::<Vec<_>>()
+        };
+        assert_eq!(help("pritn x;"), [Some("did you mean 'print'?".to_string())]);
+        // There are no return statements yet, so suggesting 'return' would only lead to another error.
+        assert_eq!(help("retrun 1;
+vra x = 1;"), [None, Some("did you mean 'var'?".to_string())]);
+        // Only an identifier right before the error looks like a keyword; `count` is a plain missing ';'.
+        assert_eq!(help("count = 1 print count;"), [None]);
+        assert_eq!(help("tru x;"), [None]);
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+use std::fmt::Display;
+
+/// A numeric range produced by `a..b` or `a..=b`, optionally followed by `step n`.
+/// Elements are computed on demand, so iterating a huge range never materializes it.
+/// The fields are private so every range has finite bounds and a usable step.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Range {
+    start: f32,
+    end: f32,
+    step: f32,
+    inclusive: bool,
+}
+
+impl Range {
+    /// Returns `None` for an infinite or NaN bound, which would make the range endless.
+    pub fn new(start: f32, end: f32, inclusive: bool) -> Option<Self> {
+        if !start.is_finite() || !end.is_finite() {
+            return None;
+        }
+        Some(Self {
+            start,
+            end,
+            step: 1.0,
+            inclusive,
+        })
+    }
+
+    pub fn start(&self) -> f32 {
+        self.start
+    }
+
+    pub fn end(&self) -> f32 {
+        self.end
+    }
+
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    pub fn inclusive(&self) -> bool {
+        self.inclusive
+    }
+
+    /// Returns `None` for a zero (or NaN) step, which would never reach the end.
+    pub fn step_by(self, step: f32) -> Option<Self> {
+        if step == 0.0 || step.is_nan() {
+            return None;
+        }
+        Some(Self { step, ..self })
+    }
+
+    pub fn len(&self) -> usize {
+        let steps = (self.end - self.start) / self.step;
+        if steps.is_nan() || steps < 0.0 {
+            return 0;
+        }
+        if self.inclusive {
+            // A huge range saturates at usize::MAX elements rather than overflowing.
+            (steps.floor() as usize).saturating_add(1)
+        } else {
+            steps.ceil() as usize
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<f32> {
+        if index < self.len() {
+            Some(self.start + index as f32 * self.step)
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(&self, value: f32) -> bool {
+        let steps = (value - self.start) / self.step;
+        steps >= 0.0 && (steps - steps.round()).abs() < f32::EPSILON && (steps.round() as usize) < self.len()
+    }
+
+    pub fn iter(&self) -> RangeIter {
+        RangeIter {
+            range: self.clone(),
+            index: 0,
+        }
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = if self.inclusive { "..=" } else { ".." };
+        write!(f, "{}{}{}", self.start, op, self.end)?;
+        if self.step != 1.0 {
+            write!(f, " step {}", self.step)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct RangeIter {
+    range: Range,
+    index: usize,
+}
+
+impl Iterator for RangeIter {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let value = self.range.get(self.index)?;
+        self.index += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.range.len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_len() {
+        assert_eq!(Range::new(0.0, 5.0, false).unwrap().len(), 5);
+        assert_eq!(Range::new(0.0, 5.0, true).unwrap().len(), 6);
+        assert_eq!(Range::new(5.0, 0.0, false).unwrap().len(), 0);
+        assert!(Range::new(5.0, 0.0, false).unwrap().is_empty());
+        assert_eq!(Range::new(0.0, 5.0, false).unwrap().step_by(2.0).unwrap().len(), 3);
+        assert_eq!(Range::new(5.0, 0.0, true).unwrap().step_by(-1.0).unwrap().len(), 6);
+        assert!(Range::new(0.0, 5.0, false).unwrap().step_by(0.0).is_none());
+        assert!(Range::new(0.0, 5.0, false).unwrap().step_by(f32::NAN).is_none());
+        assert!(Range::new(0.0, f32::INFINITY, false).is_none());
+        assert!(Range::new(f32::NAN, 5.0, true).is_none());
+        assert_eq!(Range::new(0.0, f32::MAX, true).unwrap().step_by(f32::MIN_POSITIVE).unwrap().len(), usize::MAX);
+    }
+
+    #[test]
+    fn test_contains() {
+        let range = Range::new(0.0, 10.0, false).unwrap().step_by(2.0).unwrap();
+        assert!(range.contains(0.0));
+        assert!(range.contains(8.0));
+        assert!(!range.contains(10.0));
+        assert!(!range.contains(3.0));
+        assert!(!range.contains(-2.0));
+        assert!(Range::new(0.0, 10.0, true).unwrap().contains(10.0));
+    }
+
+    #[test]
+    fn test_iter() {
+        let values: Vec<f32> = Range::new(3.0, 0.0, false).unwrap().step_by(-1.0).unwrap().iter().collect();
+        assert_eq!(values, vec![3.0, 2.0, 1.0]);
+        let values: Vec<f32> = Range::new(0.0, 1.0, true).unwrap().step_by(0.5).unwrap().iter().collect();
+        assert_eq!(values, vec![0.0, 0.5, 1.0]);
+    }
+}
@@ -2,19 +2,22 @@ use std::collections::HashMap;
 
 use once_cell::sync::Lazy;
 
-use crate::error::{error, Error};
-use crate::HAD_ERROR;
-use crate::token::{Token, TokenType};
+use crate::codes;
+use crate::diagnostic::Diagnostic;
+use crate::error::ErrorKind;
+use crate::token::{Span, Token, TokenType, TAB_WIDTH};
 
 static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     let mut m = HashMap::new();
     m.insert("and", TokenType::And);
     m.insert("class", TokenType::Class);
     m.insert("else", TokenType::Else);
+    m.insert("enum", TokenType::Enum);
     m.insert("false", TokenType::False);
     m.insert("for", TokenType::For);
     m.insert("fun", TokenType::Fun);
     m.insert("if", TokenType::If);
+    m.insert("in", TokenType::In);
     m.insert("nil", TokenType::Nil);
     m.insert("or", TokenType::Or);
     m.insert("print", TokenType::Print);
@@ -27,23 +30,34 @@ static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     m
 });
 
+/// Every keyword with the token it scans to, e.g. for suggesting one in place of a misspelled identifier.
+pub fn keywords() -> impl Iterator<Item = (&'static str, TokenType)> {
+    KEYWORDS.iter().map(|(keyword, token_type)| (*keyword, *token_type))
+}
+
 pub struct Scanner<'a> {
     source: &'a str,
     tokens: Vec<Token>,
+    // `start` and `current` are byte offsets into `source` and always sit on char boundaries.
     start: usize,
     current: usize,
+    start_line: usize,
+    start_column: usize,
     line: usize,
+    column: usize,
+    diagnostics: Vec<Diagnostic>,
 }
 
-pub fn scan_tokens(input: String) -> Result<Vec<Token>, Error> {
+/// Scans the whole input, returning every lexical error if there were any.
+pub fn scan_tokens(input: String) -> Result<Vec<Token>, Vec<Diagnostic>> {
     let mut scanner: Scanner = Scanner::new(input.as_str());
 
     scanner.scan_tokens();
 
-    if *HAD_ERROR.lock().unwrap() {
-        Err(Error::ParseError(None))
-    } else {
+    if scanner.diagnostics.is_empty() {
         Ok(scanner.tokens)
+    } else {
+        Err(scanner.diagnostics)
     }
 }
 
@@ -54,15 +68,25 @@ impl<'a> Scanner<'a> {
             tokens: Vec::new(),
             start: 0,
             current: 0,
+            start_line: 1,
+            start_column: 1,
             line: 1,
+            column: 1,
+            diagnostics: Vec::new(),
         }
     }
 
     fn scan_tokens(&mut self) {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column;
             self.scan_token()
         }
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column;
+        self.add_token(TokenType::Eof);
     }
 
     fn scan_token(&mut self) {
@@ -72,10 +96,11 @@ impl<'a> Scanner<'a> {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            ':' => self.add_token(TokenType::Colon),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
             '-' => self.add_token(TokenType::Minus),
             '+' => self.add_token(TokenType::Plus),
+            '?' => self.add_token(TokenType::Question),
             ';' => self.add_token(TokenType::Semicolon),
             '*' => self.add_token(TokenType::Star),
 
@@ -93,6 +118,20 @@ impl<'a> Scanner<'a> {
                     self.add_token(TokenType::Equal);
                 }
             }
+            '.' => {
+                if self.match_char('.') {
+                    if self.match_char('=') {
+                        self.add_token(TokenType::DotDotEqual);
+                    } else {
+                        self.add_token(TokenType::DotDot);
+                    }
+                } else {
+                    if self.peek().is_ascii_digit() {
+                        self.error(codes::MALFORMED_NUMBER, "Expect digit before '.' in number literal.");
+                    }
+                    self.add_token(TokenType::Dot);
+                }
+            }
             '<' => {
                 if self.match_char('=') {
                     self.add_token(TokenType::LessEqual);
@@ -113,71 +152,116 @@ impl<'a> Scanner<'a> {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                } else if self.match_char('*') {
+                    self.block_comment();
                 } else {
                     self.add_token(TokenType::Slash);
                 }
             }
 
-            ' ' | '\r' | '\t' => (),
-            '\n' => self.line += 1,
+            ' ' | '\r' | '\t' | '\n' => (),
 
             '"' => self.string(),
 
             _ => {
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     self.number();
-                } else if c.is_alphabetic() {
+                } else if Scanner::is_alpha(c) {
                     self.identifier();
                 } else {
-                    error(self.line, &format!("Unexpected character: {}", c));
+                    self.error(codes::UNEXPECTED_CHARACTER, &format!("Unexpected character: {}", c));
                 }
             }
         }
     }
 
+    fn error(&mut self, code: &'static str, message: &str) {
+        self.error_at(self.current_span(), code, message);
+    }
+
+    fn error_at(&mut self, span: Span, code: &'static str, message: &str) {
+        self.diagnostics.push(Diagnostic::new(ErrorKind::Lexical, code, message).with_span(span));
+    }
+
+    /// The span of the lexeme being scanned, `start..current`.
+    fn current_span(&self) -> Span {
+        Span {
+            start: self.start,
+            end: self.current,
+            start_line: self.start_line,
+            start_column: self.start_column,
+            end_line: self.line,
+            end_column: self.column,
+        }
+    }
+
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
 
+    /// Consumes one char, keeping `line` and `column` in step with `current`.
     fn advance(&mut self) -> char {
-        let result = self.source.chars().nth(self.current).unwrap();
-        self.current += 1;
+        let result = self.peek();
+        self.current += result.len_utf8();
+        match result {
+            '\n' => {
+                self.line += 1;
+                self.column = 1;
+            }
+            '\t' => self.column = (self.column - 1) / TAB_WIDTH * TAB_WIDTH + TAB_WIDTH + 1,
+            _ => self.column += 1,
+        }
         result
     }
 
     fn peek(&self) -> char {
-        if self.is_at_end() {
-            return '\0';
-        }
-        self.source.chars().nth(self.current).unwrap()
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 > self.source.len() {
-            return '\0';
-        }
-        self.source.chars().nth(self.current + 1).unwrap()
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
     }
 
     fn match_char(&mut self, expected: char) -> bool {
-        if self.is_at_end() || self.source.chars().nth(self.current) != Some(expected) {
+        if self.is_at_end() || self.peek() != expected {
             return false;
         }
 
-        self.current += 1;
+        self.advance();
         true
     }
 
+    /// Skips a `/* ... */` comment whose opening `/*` was just consumed. Block comments nest.
+    fn block_comment(&mut self) {
+        let opened_at = self.current_span();
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                let diagnostic = Diagnostic::new(ErrorKind::Lexical, codes::UNTERMINATED_COMMENT, "Unterminated block comment.")
+                    .with_span(opened_at)
+                    .with_label("comment opened here")
+                    .with_note("block comments nest, so every '/*' needs its own '*/'");
+                self.diagnostics.push(diagnostic);
+                return;
+            }
+            match self.advance() {
+                '/' if self.match_char('*') => depth += 1,
+                '*' if self.match_char('/') => depth -= 1,
+                _ => (),
+            }
+        }
+    }
+
     fn string(&mut self) {
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
-            }
             self.advance();
         }
 
         if self.is_at_end() {
-            error(self.line, "Untermited string.");
+            self.error(codes::UNTERMINATED_STRING, "Untermited string.");
+            return;
         }
 
         // The closing "
@@ -187,20 +271,110 @@ impl<'a> Scanner<'a> {
         self.add_token_literal(TokenType::String, Some(value.to_string()), None);
     }
 
+    /*
+    number         → "0x" HEX_DIGITS | "0b" BIN_DIGITS | "0o" OCT_DIGITS
+                   | DIGITS ( "." DIGITS )? ( ( "e" | "E" ) ( "+" | "-" )? DIGITS )? ;
+    DIGITS         → DIGIT ( "_"? DIGIT )* ;
+     */
     fn number(&mut self) {
-        while self.peek().is_digit(10) {
-            self.advance();
+        if &self.source[self.start..self.current] == "0" {
+            let radix = match self.peek() {
+                'x' | 'X' => Some(16),
+                'b' | 'B' => Some(2),
+                'o' | 'O' => Some(8),
+                _ => None,
+            };
+            if let Some(radix) = radix {
+                self.advance(); // consume the radix prefix
+                return self.radix_number(radix);
+            }
         }
 
-        if self.peek() == '.' && self.peek_next().is_digit(10) {
+        self.digits();
+
+        // A trailing "." is rejected, but `1..2` is a range.
+        if self.peek() == '.' && self.peek_next() != '.' {
             self.advance(); // consume the "."
-            while self.peek().is_digit(10) {
+            if !self.peek().is_ascii_digit() {
+                return self.number_error(codes::MALFORMED_NUMBER, "Expect digit after '.' in number literal.");
+            }
+            self.digits();
+        }
+
+        if matches!(self.peek(), 'e' | 'E') {
+            self.advance();
+            if matches!(self.peek(), '+' | '-') {
                 self.advance();
             }
+            if !self.peek().is_ascii_digit() {
+                return self.number_error(codes::MALFORMED_NUMBER, "Expect digits in exponent of number literal.");
+            }
+            self.digits();
+        }
+
+        if Scanner::is_alpha_numeric(self.peek()) {
+            let c = self.peek();
+            return self.number_error(codes::MALFORMED_NUMBER, &format!("Unexpected character '{}' in number literal.", c));
+        }
+
+        let text = &self.source[self.start..self.current];
+        if !Scanner::separators_between_digits(text, 10) {
+            return self.number_error(codes::MALFORMED_NUMBER, "Digit separator '_' must be between digits.");
+        }
+        match text.replace('_', "").parse::<f32>() {
+            Ok(value) if value.is_finite() => self.add_token_literal(TokenType::Number, None, Some(value)),
+            _ => self.number_error(codes::NUMBER_TOO_LARGE, "Number literal is too large."),
+        }
+    }
+
+    fn radix_number(&mut self, radix: u32) {
+        let name = match radix {
+            16 => "hexadecimal",
+            8 => "octal",
+            _ => "binary",
+        };
+        let digits_start = self.current;
+        while Scanner::is_alpha_numeric(self.peek()) {
+            self.advance();
+        }
+        let digits = &self.source[digits_start..self.current];
+
+        if let Some(c) = digits.chars().find(|&c| c != '_' && !c.is_digit(radix)) {
+            return self.error(codes::MALFORMED_NUMBER, &format!("Invalid digit '{}' in {} literal.", c, name));
+        }
+        if !digits.chars().any(|c| c.is_digit(radix)) {
+            return self.error(codes::MALFORMED_NUMBER, &format!("Expect digits in {} literal.", name));
         }
+        if !Scanner::separators_between_digits(digits, radix) {
+            return self.error(codes::MALFORMED_NUMBER, "Digit separator '_' must be between digits.");
+        }
+        match u64::from_str_radix(&digits.replace('_', ""), radix) {
+            Ok(value) => self.add_token_literal(TokenType::Number, None, Some(value as f32)),
+            Err(_) => self.error(codes::NUMBER_TOO_LARGE, "Number literal is too large."),
+        }
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_ascii_digit() || self.peek() == '_' {
+            self.advance();
+        }
+    }
 
-        let result = self.source[self.start..self.current].parse();
-        self.add_token_literal(TokenType::Number, None, Some(result.unwrap()));
+    fn separators_between_digits(text: &str, radix: u32) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        chars.iter().enumerate()
+            .filter(|(_, &c)| c == '_')
+            .all(|(i, _)| {
+                i > 0 && chars[i - 1].is_digit(radix) && chars.get(i + 1).is_some_and(|c| c.is_digit(radix))
+            })
+    }
+
+    /// Reports a malformed number and skips the rest of it so it isn't rescanned as other tokens.
+    fn number_error(&mut self, code: &'static str, message: &str) {
+        while Scanner::is_alpha_numeric(self.peek()) || self.peek() == '.' && self.peek_next() != '.' {
+            self.advance();
+        }
+        self.error(code, message);
     }
 
     fn identifier(&mut self) {
@@ -216,13 +390,18 @@ impl<'a> Scanner<'a> {
         self.add_token(token_type)
     }
 
+    fn is_alpha(c: char) -> bool {
+        c.is_alphabetic() || c == '_'
+    }
+
     fn is_alpha_numeric(c: char) -> bool {
         c.is_alphanumeric() || c == '_'
     }
 
     fn add_token_literal(&mut self, token_type: TokenType, literal_str: Option<String>, literal_num: Option<f32>) {
         let text = &self.source[self.start..self.current];
-        self.tokens.push(Token::new(token_type, text, self.line, literal_str, literal_num));
+        let span = self.current_span();
+        self.tokens.push(Token::new(token_type, text, self.start_line, literal_str, literal_num).with_span(span));
     }
 
     fn add_token(&mut self, token_type: TokenType) {
@@ -279,4 +458,126 @@ mod tests {
             Err(e) => panic!("Error: {:?}", e)
         }
     }
+
+    #[test]
+    fn test_unicode() {
+        let source = "var größe = \"ünïcødé ✓\"; _π".to_string();
+
+        match scan_tokens(source) {
+            Ok(tokens) => {
+                assert_eq!(tokens.len(), 7);
+                assert_eq!(tokens[1].token_type, TokenType::Identifier);
+                assert_eq!(tokens[1].lexeme, "größe");
+                assert_eq!(tokens[3].token_type, TokenType::String);
+                assert_eq!(tokens[3].literal_str, Some("ünïcødé ✓".to_string()));
+                assert_eq!(tokens[5].token_type, TokenType::Identifier);
+                assert_eq!(tokens[5].lexeme, "_π");
+            }
+            Err(e) => panic!("Error: {:?}", e)
+        }
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        assert!(scan_tokens("\"abc".to_string()).is_err());
+        assert!(scan_tokens("\"é".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let source = "/* outer /* inner\n */ still\n comment */ a /**/ b".to_string();
+
+        match scan_tokens(source) {
+            Ok(tokens) => {
+                assert_eq!(tokens.len(), 3);
+                assert_eq!(tokens[0].lexeme, "a");
+                assert_eq!(tokens[0].span.start_line, 3);
+                assert_eq!(tokens[1].lexeme, "b");
+                assert_eq!(tokens[2].token_type, TokenType::Eof);
+            }
+            Err(e) => panic!("Error: {:?}", e)
+        }
+
+        assert!(scan_tokens("a /* /* */".to_string()).is_err());
+    }
+
+    fn number(source: &str) -> Option<f32> {
+        scan_tokens(source.to_string()).ok().map(|tokens| {
+            assert_eq!(tokens.len(), 2);
+            tokens[0].literal_num.unwrap()
+        })
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(number("42"), Some(42.0));
+        assert_eq!(number("4.25"), Some(4.25));
+        assert_eq!(number("0xFF"), Some(255.0));
+        assert_eq!(number("0b1010"), Some(10.0));
+        assert_eq!(number("0o17"), Some(15.0));
+        assert_eq!(number("1_000_000"), Some(1_000_000.0));
+        assert_eq!(number("0xdead_beef"), Some(0xdead_beef_u32 as f32));
+        assert_eq!(number("1.5e-3"), Some(1.5e-3));
+        assert_eq!(number("2E+2"), Some(200.0));
+        assert_eq!(number("1e3"), Some(1000.0));
+    }
+
+    #[test]
+    fn test_malformed_numbers() {
+        for source in ["1.", "1.e5", ".5", "0x", "0xG", "0b102", "0o8", "1__0", "1_", "1_.5", "1e", "1e+", "12abc", "1e99", "0x1_"] {
+            assert_eq!(number(source), None, "{} should not scan", source);
+        }
+
+        match scan_tokens("1..=3".to_string()) {
+            Ok(tokens) => {
+                assert_eq!(tokens.len(), 4);
+                assert_eq!(tokens[1].token_type, TokenType::DotDotEqual);
+            }
+            Err(e) => panic!("Error: {:?}", e)
+        }
+    }
+
+    #[test]
+    fn test_spans() {
+        let source = "var é =\n\t\"a\nb\" ;".to_string();
+
+        match scan_tokens(source) {
+            Ok(tokens) => {
+                let spans: Vec<Span> = tokens.iter().map(|token| token.span).collect();
+                // `é` is two bytes but one column.
+                assert_eq!((spans[1].start, spans[1].end), (4, 6));
+                assert_eq!((spans[1].start_column, spans[1].end_column), (5, 6));
+                assert_eq!(spans[2].start_column, 7);
+                // The tab moves the string to the next tab stop and the string spans two lines.
+                assert_eq!((spans[3].start_line, spans[3].start_column), (2, 5));
+                assert_eq!((spans[3].end_line, spans[3].end_column), (3, 3));
+                assert_eq!((spans[4].start_line, spans[4].start_column), (3, 4));
+                assert_eq!((spans[5].start, spans[5].end), (17, 17));
+            }
+            Err(e) => panic!("Error: {:?}", e)
+        }
+    }
+
+    /// Benchmark: `cargo test --release scan_time_is_linear -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn scan_time_is_linear() {
+        use std::time::Instant;
+
+        let line = "var ünïcode = \"sträng\" + 12.5; // cömment\n";
+        let time = |repeat: usize| {
+            let source = line.repeat(repeat);
+            let now = Instant::now();
+            let tokens = scan_tokens(source).unwrap();
+            (now.elapsed(), tokens.len())
+        };
+
+        let small = 1 << 20; // ~1 MB at the smallest size
+        let (base, _) = time(small / line.len());
+        let (large, tokens) = time(8 * small / line.len());
+        println!("scanned {} tokens: 1x in {:?}, 8x in {:?}", tokens, base, large);
+
+        // Linear scanning grows ~8x; the old quadratic scanner grew ~64x.
+        assert!(large < base * 20, "8x input took {:?} vs {:?}", large, base);
+    }
 }
@@ -0,0 +1,90 @@
+use std::any::Any;
+use std::io::{self, IsTerminal, Write};
+
+use crate::diagnostic::{Diagnostic, SourceFile};
+
+/// Where a session sends the diagnostics it raises. Closures taking a diagnostic and its source are sinks too.
+pub trait DiagnosticSink: Send {
+    fn report(&mut self, diagnostic: &Diagnostic, source: &SourceFile);
+}
+
+/// Where `print` statements send their output, one line at a time. Closures taking the line are sinks too.
+/// `Lox::take_output` hands a sink back by its concrete type, hence the `Any` bound.
+pub trait OutputSink: Send + Any {
+    fn print(&mut self, line: &str) -> io::Result<()>;
+}
+
+/// Renders diagnostics to stderr, in colour when stderr is a terminal.
+pub struct StderrSink;
+
+impl DiagnosticSink for StderrSink {
+    fn report(&mut self, diagnostic: &Diagnostic, source: &SourceFile) {
+        eprint!("{}", diagnostic.render(source, io::stderr().is_terminal()));
+    }
+}
+
+/// Prints to stdout.
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn print(&mut self, line: &str) -> io::Result<()> {
+        writeln!(io::stdout().lock(), "{}", line)
+    }
+}
+
+/// Sends output or uncoloured diagnostics to any writer, e.g. a file or a `BufWriter` for faster output.
+pub struct WriteSink<W: Write + Send>(pub W);
+
+impl<W: Write + Send> WriteSink<W> {
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+impl<W: Write + Send + 'static> OutputSink for WriteSink<W> {
+    fn print(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.0, "{}", line)
+    }
+}
+
+impl<W: Write + Send> DiagnosticSink for WriteSink<W> {
+    fn report(&mut self, diagnostic: &Diagnostic, source: &SourceFile) {
+        // Like `eprint!`, there is nowhere left to report a failure to write a diagnostic.
+        let _ = self.0.write_all(diagnostic.render(source, false).as_bytes());
+    }
+}
+
+impl<F: FnMut(&Diagnostic, &SourceFile) + Send> DiagnosticSink for F {
+    fn report(&mut self, diagnostic: &Diagnostic, source: &SourceFile) {
+        self(diagnostic, source)
+    }
+}
+
+impl<F: FnMut(&str) + Send + 'static> OutputSink for F {
+    fn print(&mut self, line: &str) -> io::Result<()> {
+        self(line);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codes;
+    use crate::error::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn test_write_sink() {
+        let source = SourceFile {
+            name: "test.lox".to_string(),
+            text: "print x;".to_string(),
+        };
+        let mut sink = WriteSink(Vec::new());
+        sink.print("1").unwrap();
+        sink.report(&Diagnostic::new(ErrorKind::Resolution, codes::UNDEFINED_VARIABLE, "Undefined variable 'x'."), &source);
+        sink.print("2").unwrap();
+        let written = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!(written, "1\nerror[E0200]: Undefined variable 'x'.\n2\n");
+    }
+}
@@ -1,23 +1,51 @@
-use crate::expr::Expr;
-use crate::token::Token;
+use crate::expr::{Expr, Pattern};
+use crate::token::{Span, Token};
+use crate::typecheck::Type;
 
 /*
 program        → declaration* EOF ;
 
-declaration    → varDecl
+declaration    → enumDecl
+               | varDecl
                | statement ;
 
+enumDecl       → "enum" IDENTIFIER "{" ( variant ( "," variant )* ","? )? "}" ;
+variant        → IDENTIFIER ( "(" ( IDENTIFIER ( "," IDENTIFIER )* )? ")" )? ;
+
+varDecl        → "var" IDENTIFIER ( ":" type )? ( "=" expression )? ";"
+               | "var" "(" pattern ( "," pattern )* ")" "=" expression ";" ;
+
 statement      → exprStmt
+               | forStmt
                | printStmt
                | block;
 
 block          → "{" declaration* "}" ;
 exprStmt       → expression ";" ;
+forStmt        → "for" "(" pattern ( "," pattern )* "in" expression ")" statement ;
 printStmt      → "print" expression ";" ;
  */
+/// Each statement ends with its span, from its leading keyword through its `;` or closing brace.
 pub enum Stmt {
-    Expression(Expr),
-    Print(Expr),
-    Var(Token, Option<Expr>),
-    Block(Vec<Stmt>),
-}
\ No newline at end of file
+    Expression(Expr, Span),
+    Print(Expr, Span),
+    Var(Token, Option<Type>, Option<Expr>, Span),
+    VarPattern(Pattern, Expr, Span),
+    Block(Vec<Stmt>, Span),
+    Enum(Token, Vec<(Token, Option<Vec<Token>>)>, Span),
+    ForIn(Pattern, Expr, Box<Stmt>, Span),
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Expression(_, span)
+            | Stmt::Print(_, span)
+            | Stmt::Var(_, _, _, span)
+            | Stmt::VarPattern(_, _, span)
+            | Stmt::Block(_, span)
+            | Stmt::Enum(_, _, span)
+            | Stmt::ForIn(_, _, _, span) => *span,
+        }
+    }
+}
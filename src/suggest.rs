@@ -0,0 +1,60 @@
+/// The number of single-char insertions, deletions, substitutions and swaps of adjacent chars
+/// that turn `a` into `b`. Swaps count as one edit since `pritn` is as likely a typo as `prnt`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i chars of `a` and the first j chars of `b`.
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    distances[0] = (0..=b.len()).collect();
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution.min(distances[i - 1][j] + 1).min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// The candidate closest to `name`, if any is close enough to be a likely typo:
+/// at most one edit per three chars of `name`, and at least one edit for short names.
+/// A one-char name is never a likely typo: any other one-char name would be one edit away.
+/// Ties go to the alphabetically first candidate so suggestions don't depend on hash order.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let length = name.chars().count();
+    if length < 2 {
+        return None;
+    }
+    let limit = (length / 3).max(1);
+    candidates.into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(edit_distance("pritn", "print"), 1);
+        assert_eq!(edit_distance("", "var"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+
+        assert_eq!(closest("pritn", ["print", "return"]), Some("print"));
+        assert_eq!(closest("countr", ["count", "counter"]), Some("count"));
+        assert_eq!(closest("ab", ["ac", "ad"]), Some("ac"));
+        assert_eq!(closest("b", ["a", "c"]), None);
+        assert_eq!(closest("total", ["x", "y"]), None);
+        assert_eq!(closest("x", ["x"]), None);
+    }
+}
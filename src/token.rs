@@ -1,13 +1,50 @@
 use std::fmt;
 use std::fmt::Display;
 
-#[derive(Clone)]
+/// Where a piece of source text lives: byte offsets `start..end` plus the 1-based line and
+/// column of both ends. Columns count chars rather than bytes and expand tabs to `TAB_WIDTH` stops;
+/// the end column is the one just past the last char.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+pub const TAB_WIDTH: usize = 4;
+
+impl Span {
+    /// The span covering `self` through `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            end_line: other.end_line,
+            end_column: other.end_column,
+            ..self
+        }
+    }
+
+    /// The empty span right after `self`, where text can be inserted.
+    pub fn after(self) -> Span {
+        Span {
+            start: self.end,
+            start_line: self.end_line,
+            start_column: self.end_column,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
-    pub line: usize,
     pub literal_str: Option<String>,
     pub literal_num: Option<f32>,
+    pub span: Span,
 }
 
 impl Token {
@@ -15,11 +52,19 @@ impl Token {
         Self {
             token_type,
             lexeme: lexeme.to_string(),
-            line,
             literal_str,
             literal_num,
+            span: Span {
+                start_line: line,
+                end_line: line,
+                ..Span::default()
+            },
         }
     }
+
+    pub fn with_span(self, span: Span) -> Self {
+        Self { span, ..self }
+    }
 }
 
 impl Display for Token {
@@ -39,17 +84,21 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    Colon,
     Comma,
     Dot,
     Minus,
     Plus,
+    Question,
     Semicolon,
     Slash,
     Star,
 
-    // One or two character tokens.
+    // One, two or three character tokens.
     Bang,
     BangEqual,
+    DotDot,
+    DotDotEqual,
     Equal,
     EqualEqual,
     Greater,
@@ -66,10 +115,12 @@ pub enum TokenType {
     And,
     Class,
     Else,
+    Enum,
     False,
     Fun,
     For,
     If,
+    In,
     Nil,
     Or,
     Print,
@@ -79,7 +130,6 @@ pub enum TokenType {
     True,
     Var,
     While,
-    PRINT,
 
     Eof,
 }
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
+
+use crate::codes;
+use crate::diagnostic::Diagnostic;
+use crate::error::ErrorKind;
+use crate::expr::{Expr, Pattern};
+use crate::stmt::Stmt;
+use crate::token::{Span, Token, TokenType};
+
+/*
+type           → ( "Number" | "String" | "Bool" | "Range" | "Nil" | "Any" | "(" type ( "," type )* ")" ) "?"? ;
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Number,
+    String,
+    Bool,
+    Range,
+    Nil,
+    Any, // Unannotated values stay dynamically typed
+    Optional(Box<Type>),
+    Tuple(Vec<Type>),
+}
+
+impl Type {
+    pub fn from_name(name: &str) -> Option<Type> {
+        match name {
+            "Number" => Some(Type::Number),
+            "String" => Some(Type::String),
+            "Bool" => Some(Type::Bool),
+            "Range" => Some(Type::Range),
+            "Nil" => Some(Type::Nil),
+            "Any" => Some(Type::Any),
+            _ => None,
+        }
+    }
+
+    pub fn optional(self) -> Type {
+        match self {
+            Type::Nil | Type::Any | Type::Optional(_) => self,
+            _ => Type::Optional(Box::new(self)),
+        }
+    }
+
+    fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Optional(_), Type::Nil) => true,
+            (Type::Optional(inner), Type::Optional(other)) => inner.accepts(other),
+            (Type::Optional(inner), other) => inner.accepts(other),
+            (Type::Tuple(lhs), Type::Tuple(rhs)) => {
+                lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| lhs.accepts(rhs))
+            }
+            (lhs, rhs) => lhs == rhs,
+        }
+    }
+
+    fn is(&self, expected: &Type) -> bool {
+        *self == Type::Any || self == expected
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Optional(inner) => write!(f, "{}?", inner),
+            Type::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(Type::to_string).collect();
+                write!(f, "({})", elements.join(", "))
+            }
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// How `bind_pattern` treats the names matched by a pattern.
+#[derive(Clone, Copy, PartialEq)]
+enum Binding {
+    Declare, // `var` patterns stay dynamically typed like a plain `var`
+    DeclareTyped, // Loop variables take the element type
+    Assign,
+}
+
+struct TypeChecker {
+    scopes: Vec<HashMap<String, (Type, Span)>>, // Declared type and where it was declared
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Reports type mismatches that would otherwise only surface while interpreting.
+pub fn check(statements: &[Stmt]) -> Result<(), Vec<Diagnostic>> {
+    let mut checker = TypeChecker::new();
+
+    for stmt in statements {
+        checker.check_stmt(stmt);
+    }
+
+    if checker.diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.diagnostics)
+    }
+}
+
+impl TypeChecker {
+    fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(expr, _) | Stmt::Print(expr, _) => {
+                self.check_expr(expr);
+            }
+            Stmt::Var(name, annotation, initializer, _) => {
+                let value_type = match initializer {
+                    Some(expr) => self.check_expr(expr),
+                    None => Type::Nil,
+                };
+                let declared = match annotation {
+                    Some(declared) => {
+                        if !declared.accepts(&value_type) {
+                            let message = format!("Cannot initialize '{}' of type {} with a value of type {}.", name.lexeme, declared, value_type);
+                            let diagnostic = match initializer {
+                                Some(expr) => Diagnostic::new(ErrorKind::Type, codes::MISMATCHED_TYPES, &message)
+                                    .with_span(expr.span())
+                                    .with_label(&format!("expected {}, found {}", declared, value_type))
+                                    .with_secondary(name.span, &format!("'{}' declared as {} here", name.lexeme, declared)),
+                                None => Diagnostic::new(ErrorKind::Type, codes::MISMATCHED_TYPES, &message)
+                                    .with_span(name.span)
+                                    .with_note("a variable without an initializer starts out as nil"),
+                            };
+                            self.report(diagnostic);
+                        }
+                        declared.clone()
+                    }
+                    None => Type::Any,
+                };
+                self.declare(name, declared);
+            }
+            Stmt::VarPattern(pattern, initializer, _) => {
+                let value_type = self.check_expr(initializer);
+                self.bind_pattern(pattern, &value_type, Binding::Declare);
+            }
+            Stmt::Enum(_, variants, _) => {
+                for (variant, _) in variants {
+                    self.declare(variant, Type::Any);
+                }
+            }
+            Stmt::Block(stmts, _) => {
+                self.scopes.push(HashMap::new());
+                for stmt in stmts {
+                    self.check_stmt(stmt);
+                }
+                self.scopes.pop();
+            }
+            Stmt::ForIn(pattern, iterable, body, _) => {
+                let iterable_type = self.check_expr(iterable);
+                let element_type = match iterable_type {
+                    Type::String => Type::String,
+                    Type::Range => Type::Number,
+                    // Only a tuple whose elements all have one type gives the loop variable a known type.
+                    Type::Tuple(ref types) => match types.split_first() {
+                        Some((first, rest)) if rest.iter().all(|element| element == first) => first.clone(),
+                        _ => Type::Any,
+                    },
+                    Type::Any => Type::Any,
+                    _ => {
+                        self.error(pattern.first_token(), codes::NOT_ITERABLE, &format!("Value of type {} is not iterable.", iterable_type));
+                        Type::Any
+                    }
+                };
+                self.scopes.push(HashMap::new());
+                self.bind_pattern(pattern, &element_type, Binding::DeclareTyped);
+                self.check_stmt(body);
+                self.scopes.pop();
+            }
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::LiteralNum(..) => Type::Number,
+            Expr::LiteralStr(Some(_), _) => Type::String,
+            Expr::LiteralStr(None, _) => Type::Nil,
+            Expr::LiteralBool(..) => Type::Bool,
+            Expr::Grouping(expr, _) => self.check_expr(expr),
+            Expr::Call(callee, paren, arguments) => {
+                let callee_type = self.check_expr(callee);
+                for argument in arguments {
+                    self.check_expr(argument);
+                }
+                if callee_type != Type::Any {
+                    self.error(paren, codes::NOT_CALLABLE, &format!("Value of type {} is not callable.", callee_type));
+                }
+                Type::Any
+            }
+            Expr::Tuple(elements, _) => Type::Tuple(elements.iter().map(|element| self.check_expr(element)).collect()),
+            Expr::Range(start_expr, op, end_expr, step) => {
+                let start = self.check_expr(start_expr);
+                let end = self.check_expr(end_expr);
+                self.expect_numbers(op, &[(start_expr.span(), &start), (end_expr.span(), &end)]);
+                if let Some(step) = step {
+                    let step = self.check_expr(step);
+                    self.expect(op, &step, Type::Number, "Range step must be a number.");
+                }
+                Type::Range
+            }
+            Expr::Unary(op, expr) => {
+                let right = self.check_expr(expr);
+                match op.token_type {
+                    TokenType::Minus => self.expect(op, &right, Type::Number, "Operand must be a number."),
+                    TokenType::Bang => self.expect(op, &right, Type::Bool, "Operand must be a boolean."),
+                    _ => Type::Any,
+                }
+            }
+            Expr::Binary(left, op, right) => self.check_binary(left, op, right),
+            Expr::Variable(name) => self.lookup(name),
+            Expr::Assign(name, value) => {
+                let value_type = self.check_expr(value);
+                self.check_assignment(name, &value_type);
+                value_type
+            }
+            Expr::AssignPattern(pattern, value) => {
+                let value_type = self.check_expr(value);
+                self.bind_pattern(pattern, &value_type, Binding::Assign);
+                value_type
+            }
+        }
+    }
+
+    /// Declares (or checks assignments to) every name in `pattern`, reporting arity mismatches it can prove.
+    fn bind_pattern(&mut self, pattern: &Pattern, value_type: &Type, binding: Binding) {
+        match (pattern, value_type) {
+            (Pattern::Name(name), _) if binding == Binding::Declare => self.declare(name, Type::Any),
+            (Pattern::Name(name), _) if binding == Binding::DeclareTyped => self.declare(name, value_type.clone()),
+            (Pattern::Name(name), _) => self.check_assignment(name, value_type),
+            (Pattern::Tuple(patterns), Type::Tuple(types)) if patterns.len() == types.len() => {
+                for (pattern, value_type) in patterns.iter().zip(types) {
+                    self.bind_pattern(pattern, value_type, binding);
+                }
+            }
+            (Pattern::Tuple(patterns), Type::Any) => {
+                for pattern in patterns {
+                    self.bind_pattern(pattern, &Type::Any, binding);
+                }
+            }
+            (Pattern::Tuple(patterns), _) => {
+                self.error(pattern.first_token(), codes::INVALID_PATTERN, &format!("Cannot destructure a value of type {} into {} variables.", value_type, patterns.len()));
+                for pattern in patterns {
+                    self.bind_pattern(pattern, &Type::Any, binding);
+                }
+            }
+            (Pattern::Variant(name, patterns), _) => {
+                if *value_type != Type::Any {
+                    self.error(name, codes::INVALID_PATTERN, &format!("Cannot destructure a value of type {} with pattern {}.", value_type, name.lexeme));
+                }
+                for pattern in patterns {
+                    self.bind_pattern(pattern, &Type::Any, binding);
+                }
+            }
+        }
+    }
+
+    fn check_assignment(&mut self, name: &Token, value_type: &Type) {
+        let Some((declared, declared_at)) = self.declaration(name).cloned() else {
+            return;
+        };
+        if !declared.accepts(value_type) {
+            let diagnostic = Diagnostic::new(ErrorKind::Type, codes::MISMATCHED_TYPES, &format!("Cannot assign a value of type {} to '{}' of type {}.", value_type, name.lexeme, declared))
+                .with_span(name.span)
+                .with_label(&format!("expected {}, found {}", declared, value_type))
+                .with_secondary(declared_at, &format!("'{}' declared as {} here", name.lexeme, declared));
+            self.report(diagnostic);
+        }
+    }
+
+    fn check_binary(&mut self, left_expr: &Expr, op: &Token, right_expr: &Expr) -> Type {
+        let left = self.check_expr(left_expr);
+        let right = self.check_expr(right_expr);
+        let operands = [(left_expr.span(), &left), (right_expr.span(), &right)];
+        match op.token_type {
+            TokenType::Minus | TokenType::Slash | TokenType::Star => {
+                self.expect_numbers(op, &operands);
+                Type::Number
+            }
+            TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual => {
+                self.expect_numbers(op, &operands);
+                Type::Bool
+            }
+            TokenType::Plus => match (&left, &right) {
+                (Type::Number, Type::Number) => Type::Number,
+                (Type::String, Type::String) => Type::String,
+                (Type::Any, Type::Number | Type::String) => right,
+                (Type::Number | Type::String, Type::Any) => left,
+                (Type::Any, Type::Any) => Type::Any,
+                _ => {
+                    self.operand_error(op, "Operands must be both numbers or strings.", &operands);
+                    Type::Any
+                }
+            },
+            TokenType::In => {
+                match (&left, &right) {
+                    (Type::Number | Type::Any, Type::Range) | (Type::String | Type::Any, Type::String) | (_, Type::Any) => {}
+                    _ => self.operand_error(op, "Operands must be a number and a range, or two strings.", &operands),
+                }
+                Type::Bool
+            }
+            TokenType::BangEqual | TokenType::EqualEqual => Type::Bool,
+            _ => Type::Any,
+        }
+    }
+
+    fn expect(&mut self, op: &Token, actual: &Type, expected: Type, message: &str) -> Type {
+        if !actual.is(&expected) {
+            self.error(op, codes::INVALID_OPERANDS, message);
+        }
+        expected
+    }
+
+    fn expect_numbers(&mut self, op: &Token, operands: &[(Span, &Type)]) {
+        if operands.iter().any(|(_, operand)| !operand.is(&Type::Number)) {
+            self.operand_error(op, "Operands must be numbers.", operands);
+        }
+    }
+
+    /// Reports a bad operator application, labelling each operand with its type.
+    fn operand_error(&mut self, op: &Token, message: &str, operands: &[(Span, &Type)]) {
+        let mut diagnostic = Diagnostic::new(ErrorKind::Type, codes::INVALID_OPERANDS, message).with_span(op.span);
+        for (span, operand) in operands {
+            if **operand != Type::Any {
+                diagnostic = diagnostic.with_secondary(*span, &format!("this is {}", operand));
+            }
+        }
+        if operands.iter().any(|(_, operand)| matches!(operand, Type::Optional(_))) {
+            diagnostic = diagnostic.with_note("optional values may be nil");
+        }
+        self.report(diagnostic);
+    }
+
+    fn declare(&mut self, name: &Token, declared: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), (declared, name.span));
+        }
+    }
+
+    fn declaration(&self, name: &Token) -> Option<&(Type, Span)> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&name.lexeme))
+    }
+
+    fn lookup(&self, name: &Token) -> Type {
+        // Names defined outside the checked statements (e.g. in an earlier prompt line) are dynamic.
+        self.declaration(name).map_or(Type::Any, |(declared, _)| declared.clone())
+    }
+
+    fn error(&mut self, token: &Token, code: &'static str, message: &str) {
+        self.report(Diagnostic::new(ErrorKind::Type, code, message).with_span(token.span));
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::scanner::scan_tokens;
+
+    use super::*;
+
+    fn check_source(source: &str) -> Result<(), Vec<Diagnostic>> {
+        let tokens = scan_tokens(source.to_string())?;
+        let (stmts, diagnostics) = Parser::new(tokens).parse();
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+        check(&stmts)
+    }
+
+    #[test]
+    fn test_operand_mismatch() {
+        assert!(check_source("print \"a\" - 1;").is_err());
+        assert!(check_source("print 1 + \"a\";").is_err());
+        assert!(check_source("print -\"a\";").is_err());
+        assert!(check_source("print 1 < 2;").is_ok());
+        assert!(check_source("print \"a\" + \"b\";").is_ok());
+    }
+
+    #[test]
+    fn test_unannotated_is_dynamic() {
+        assert!(check_source("var a = \"a\"; a = 1; print a - 1;").is_ok());
+        assert!(check_source("print b - 1;").is_ok());
+    }
+
+    #[test]
+    fn test_annotations() {
+        assert!(check_source("var x: Number = 1; x = 2;").is_ok());
+        assert!(check_source("var x: Number = \"1\";").is_err());
+        assert!(check_source("var x: Number = 1; x = \"a\";").is_err());
+        assert!(check_source("var x: String; ").is_err());
+        assert!(check_source("var x: Number? = nil; x = 1;").is_ok());
+        assert!(check_source("var x: Number? = 1; print x - 1;").is_err());
+        assert!(check_source("var x: Number = 1; print x - \"a\";").is_err());
+    }
+
+    #[test]
+    fn test_block_scopes() {
+        assert!(check_source("var x: Number = 1; { var x: String = \"a\"; x = \"b\"; } x = 2;").is_ok());
+        assert!(check_source("var x: Number = 1; { x = \"b\"; }").is_err());
+    }
+
+    #[test]
+    fn test_for_in() {
+        assert!(check_source("for (c in \"abc\") print c + \"!\";").is_ok());
+        assert!(check_source("for (c in \"abc\") print c - 1;").is_err());
+        assert!(check_source("var n: Number = 3; for (c in n) print c;").is_err());
+        assert!(check_source("for (i in 0..3) print i - 1;").is_ok());
+        assert!(check_source("for (i in 0..3) print i + \"a\";").is_err());
+    }
+
+    #[test]
+    fn test_tuples() {
+        assert!(check_source("var (a, b) = (1, \"x\"); (a, b) = (b, a);").is_ok());
+        assert!(check_source("var (a, b) = (1, 2, 3);").is_err());
+        assert!(check_source("var (a, b) = 1;").is_err());
+        assert!(check_source("var p: (Number, String) = (1, \"x\");").is_ok());
+        assert!(check_source("var p: (Number, String) = (\"x\", 1);").is_err());
+        assert!(check_source("var x: Number = 1; var y = 2; (x, y) = (\"a\", 3);").is_err());
+        assert!(check_source("for (a, b in (1, 2)) print a;").is_err());
+        assert!(check_source("for (a, b in ((1, 2), (3, 4))) print a - b;").is_ok());
+        assert!(check_source("for (x in (1, \"a\")) print x;").is_ok());
+        assert!(check_source("for (x in (1, 2)) print x + \"a\";").is_err());
+        // Parentheses around one pattern or type only group it; a trailing comma makes a 1-tuple.
+        assert!(check_source("var (a) = 1; print a - 1;").is_ok());
+        assert!(check_source("var (a,) = (1,); print a - 1;").is_ok());
+        assert!(check_source("var (a,) = 1;").is_err());
+        assert!(check_source("var (a, b,) = (1, 2);").is_ok());
+        assert!(check_source("var x: (Number) = 1;").is_ok());
+        assert!(check_source("var x: (Number,) = (1,);").is_ok());
+        assert!(check_source("var x: (Number,) = 1;").is_err());
+        assert!(check_source("var x: (Number)? = nil;").is_ok());
+    }
+
+    #[test]
+    fn test_enums() {
+        assert!(check_source("enum Shape { Circle(r), Empty } var Circle(r) = Circle(1); print r - 1;").is_ok());
+        assert!(check_source("var Circle(r) = 1;").is_err());
+        assert!(check_source("print \"a\"(1);").is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        assert!(check_source("var r: Range = 0..=10 step 2; print 3 in r;").is_ok());
+        assert!(check_source("var r = \"a\"..3;").is_err());
+        assert!(check_source("print \"a\" in 0..3;").is_err());
+        assert!(check_source("print 1 in 3;").is_err());
+    }
+}
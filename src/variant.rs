@@ -0,0 +1,31 @@
+use crate::interpreter::Value;
+
+/// A value built from an `enum` declaration, e.g. `Rect(1, 2)` or `Empty`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Variant {
+    pub enum_name: String,
+    pub name: String,
+    pub fields: Vec<Value>,
+}
+
+/// The constructor function bound to a variant that declares fields, e.g. `Circle(r)`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct VariantConstructor {
+    pub enum_name: String,
+    pub name: String,
+    pub params: Vec<String>,
+}
+
+impl VariantConstructor {
+    pub fn arity(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn construct(&self, fields: Vec<Value>) -> Variant {
+        Variant {
+            enum_name: self.enum_name.clone(),
+            name: self.name.clone(),
+            fields,
+        }
+    }
+}